@@ -5,7 +5,8 @@ use ordered_float::OrderedFloat;
 use rand::prelude::SmallRng;
 use rand::{Rng, RngCore, SeedableRng};
 use sparrow::config::*;
-use sparrow::optimizer::lbf::LBFBuilder;
+use sparrow::optimizer::lbf::construct_best;
+use sparrow::util::symmetry::collapse_symmetric_orientations;
 use sparrow::optimizer::separator::Separator;
 use sparrow::util::io;
 use std::env::args;
@@ -16,7 +17,8 @@ use std::time::{Duration, Instant};
 use anyhow::Result;
 use jagua_rs::io::import::Importer;
 use jagua_rs::io::svg::s_layout_to_svg;
-use sparrow::consts::{DEFAULT_COMPRESS_TIME_RATIO, DEFAULT_EXPLORE_TIME_RATIO, DRAW_OPTIONS, LBF_SAMPLE_CONFIG};
+use sparrow::consts::{DEFAULT_COMPRESS_TIME_RATIO, DEFAULT_EXPLORE_TIME_RATIO, DRAW_OPTIONS};
+use sparrow::optimizer::compact::compaction_phase;
 use sparrow::optimizer::compress::compression_phase;
 use sparrow::optimizer::explore::exploration_phase;
 use sparrow::util::listener::DummySolListener;
@@ -64,6 +66,10 @@ fn main() -> Result<()> {
 
     let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
     let instance = jagua_rs::probs::spp::io::import(&importer, &ext_intance)?;
+    let instance = match config.symmetry_tolerance {
+        Some(tolerance) => collapse_symmetric_orientations(&instance, tolerance),
+        None => instance,
+    };
 
     let mut final_solutions = vec![];
 
@@ -81,7 +87,9 @@ fn main() -> Result<()> {
 
                 s.spawn(move |_| {
                     let mut next_rng = || SmallRng::seed_from_u64(rng.next_u64());
-                    let builder = LBFBuilder::new(instance.clone(), next_rng(), LBF_SAMPLE_CONFIG).construct();
+                    let start_constr = Instant::now();
+                    let builder = construct_best(&instance, next_rng(), &config.constr_cfg);
+                    let constr_time = start_constr.elapsed();
                     let mut expl_separator = Separator::new(builder.instance, builder.prob, next_rng(), config.expl_cfg.separator_config);
 
                     terminator.new_timeout(time_limit.mul_f32(DEFAULT_EXPLORE_TIME_RATIO));
@@ -93,9 +101,14 @@ fn main() -> Result<()> {
                     terminator.new_timeout(time_limit.mul_f32(DEFAULT_COMPRESS_TIME_RATIO));
                     let mut cmpr_separator = Separator::new(expl_separator.instance, expl_separator.prob, next_rng(), config.cmpr_cfg.separator_config);
                     let cmpr_sol = compression_phase(&instance, &mut cmpr_separator, final_explore_sol, &mut DummySolListener, &terminator, &config.cmpr_cfg);
+                    let cmpr_sol = match &config.cmpr_cfg.compaction {
+                        Some(compaction_config) => compaction_phase(&instance, &cmpr_sol, &mut DummySolListener, compaction_config),
+                        None => cmpr_sol,
+                    };
 
-                    println!("[BENCH] [id:{:>3}] finished, expl: {:.3}% ({}s), cmpr: {:.3}% (+{:.3}%) ({}s)",
+                    println!("[BENCH] [id:{:>3}] finished, constr: {:.3}s, expl: {:.3}% ({}s), cmpr: {:.3}% (+{:.3}%) ({}s)",
                              bench_idx,
+                             constr_time.as_secs_f32(),
                              final_explore_sol.density(&instance) * 100.0, time_limit.mul_f32(DEFAULT_EXPLORE_TIME_RATIO).as_secs(),
                              cmpr_sol.density(&instance) * 100.0,
                              cmpr_sol.density(&instance) * 100.0 - final_explore_sol.density(&instance) * 100.0,
@@ -108,12 +121,13 @@ fn main() -> Result<()> {
                         log::Level::Info,
                     ).expect(&*format!("could not write svg output of bench {}", bench_idx));
 
-                    *sol_slice = Some(cmpr_sol);
+                    *sol_slice = Some((cmpr_sol, constr_time.as_secs_f32()));
                 })
             }
         });
         final_solutions.extend(iter_solutions.into_iter().flatten());
     }
+    let (final_solutions, final_constr_times): (Vec<_>, Vec<f32>) = final_solutions.into_iter().unzip();
 
     //print statistics about the solutions, print best, worst, median and average
     let (final_widths, final_usages): (Vec<f32>, Vec<f32>) = final_solutions
@@ -154,6 +168,11 @@ fn main() -> Result<()> {
     println!("best:   {:.3}", final_usages.iter().max_by_key(|&x| OrderedFloat(*x)).unwrap());
     println!("avg:    {:.3}", calculate_average(&final_usages));
     println!("stddev: {:.3}", calculate_stddev(&final_usages));
+    println!("---- CONSTRUCTION TIME STATS (s) ----");
+    println!("slowest: {:.3}", final_constr_times.iter().max_by_key(|&x| OrderedFloat(*x)).unwrap());
+    println!("median:  {:.3}", calculate_median(&final_constr_times));
+    println!("fastest: {:.3}", final_constr_times.iter().min_by_key(|&x| OrderedFloat(*x)).unwrap());
+    println!("avg:     {:.3}", calculate_average(&final_constr_times));
     println!("======================");
     println!("[BENCH] system time: {}", jiff::Timestamp::now());
 
@@ -1,12 +1,21 @@
+use crate::optimizer::disruption::DisruptionOperator;
+use crate::consts::{GLS_WEIGHT_DECAY, GLS_WEIGHT_MAX_INC_RATIO, GLS_WEIGHT_MIN_INC_RATIO, LBF_DEFAULT_STRATEGY, LBF_SAMPLE_CONFIG};
+use crate::optimizer::lbf::ConstructionStrategy;
 use crate::optimizer::separator::SeparatorConfig;
-use crate::sample::search::SampleConfig;
+use crate::optimizer::split::{SplitConfig, SplitPositionStrategy};
+use crate::quantify::weights::{GLSWeightConfig, WeightUpdateRule};
+use crate::quantify::OverlapQuantifier;
+use crate::sample::refine::RefinerType;
+use crate::sample::search::{ContainerSampling, SampleConfig};
 use jagua_rs::collision_detection::CDEConfig;
 use jagua_rs::geometry::fail_fast::SPSurrogateConfig;
+use std::num::NonZeroUsize;
 use std::time::Duration;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SparrowConfig {
     pub rng_seed: Option<usize>,
+    pub constr_cfg: ConstructionConfig,
     pub expl_cfg: ExplorationConfig,
     pub cmpr_cfg: CompressionConfig,
     /// Configuration for the collision detection engine.
@@ -20,16 +29,121 @@ pub struct SparrowConfig {
     /// Disabled if `None`.
     /// See [`jagua_rs::io::parser::Parser::new`] for more details.
     pub min_item_separation: Option<f32>,
+    /// Defines the tolerance (ratio of the item's area) within which rotated items are considered identical.
+    /// Equivalent orientations are collapsed into one at import time.
+    /// Disabled if `None`.
+    /// See [`crate::util::symmetry::collapse_symmetric_orientations`] for more details.
+    pub symmetry_tolerance: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConstructionConfig {
+    /// Constructive strategies to build an initial solution with (in parallel), the narrowest result is kept
+    pub strategies: &'static [ConstructionStrategy],
+    pub sample_config: SampleConfig,
+    /// Seeds high-demand items in periodic lattice packings before the constructive heuristic, disabled if `None`
+    pub lattice: Option<LatticeConfig>,
+    /// Splits the samples of every placement over this many partitions, which are sampled in parallel.
+    /// The best samples of all partitions are then refined sequentially. Results are reproducible for a given seed and
+    /// number of partitions, but differ from the sequential search, which draws its samples from a single rng.
+    /// Sequential if `None`
+    pub search_partitions: Option<usize>,
+}
+
+/// See [`Lattice`](crate::optimizer::lattice::Lattice)
+#[derive(Debug, Clone, Copy)]
+pub struct LatticeConfig {
+    /// Minimum demand of an item to be seeded in a lattice
+    pub min_demand: usize,
+    /// Number of rotations considered for items that can rotate continuously
+    pub n_continuous_rotations: usize,
+    /// Number of shears of the second lattice vector that are considered
+    pub n_shear_samples: usize,
+    /// Number of starting positions to find the densest contact between the two items of a double lattice
+    pub n_contact_samples: usize,
+    /// Number of bisections to find the smallest lattice vectors and contacts
+    pub n_bisections: usize,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct ExplorationConfig {
-    pub shrink_step: f32,
+    pub shrink_step: ShrinkStepStrategy,
     pub time_limit: Duration,
     pub max_conseq_failed_attempts: Option<usize>,
     pub solution_pool_distribution_stddev: f32,
     pub separator_config: SeparatorConfig,
-    pub large_item_ch_area_cutoff_percentile: f32
+    pub large_item_ch_area_cutoff_percentile: f32,
+    pub disruption: DisruptionConfig,
+    /// Where the strip is split when shrinking after a feasible solution was found
+    pub split: SplitConfig,
+    /// Ruin and recreate as an alternative to disruption when restarts from the solution pool keep failing, disabled if `None`
+    pub ruin_recreate: Option<RuinRecreateConfig>,
+    /// Strategic oscillation: temporarily widen the strip when exploration is stuck, disabled if `None`
+    pub oscillation: Option<OscillationConfig>,
+    /// Persistent pool of diverse feasible solutions which are recombined when exploration is stuck, disabled if `None`
+    pub elite: Option<EliteConfig>,
+    /// Decomposition of the strip into sliding windows, for very large instances. Disabled if `None`
+    pub window: Option<WindowConfig>,
+    /// Merges complementary pairs of items into composite items for the exploration phase, disabled if `None`
+    pub clustering: Option<ClusterConfig>,
+}
+
+/// See [`cluster_complementary_items`](crate::optimizer::cluster::cluster_complementary_items)
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterConfig {
+    /// Minimum utilization (area of both items divided by the area of their convex hull) of a pair to be merged
+    pub min_utilization: f32,
+    /// Number of starting positions to find the densest contact between the two items of a pair
+    pub n_contact_samples: usize,
+    /// Number of bisections to push the two items of a pair together
+    pub n_bisections: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EliteConfig {
+    /// Maximum number of solutions in the pool, the widest is evicted when exceeded
+    pub capacity: usize,
+    /// Minimum distance (see [`layout_distance`](crate::optimizer::elite::layout_distance)) between members of the pool
+    pub min_distance: f32,
+    /// Recombine two members instead of disrupting every time this many consecutive attempts have failed at the current width
+    pub recombine_after_fails: NonZeroUsize,
+    /// Width of the region copied from the donor, as a ratio of the strip width
+    pub region_width_ratio: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OscillationConfig {
+    /// Widen the strip every time this many consecutive attempts have failed since the last feasible solution.
+    /// A backoff of the [`ShrinkStepStrategy`] due at the same attempt takes precedence.
+    pub trigger_after_fails: NonZeroUsize,
+    /// Width of the widened strip, as a ratio above the best feasible width
+    pub widen_ratio: f32,
+    /// Maximum number of times the strip is widened during the exploration phase
+    pub max_oscillations: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RuinRecreateConfig {
+    /// Ruin and recreate instead of disrupting every time this many consecutive attempts have failed at the current width
+    pub trigger_after_fails: NonZeroUsize,
+    /// Width of the ruined window, as a ratio of the strip width
+    pub window_width_ratio: f32,
+    /// Sample configuration used to reinsert the removed items
+    pub sample_config: SampleConfig,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisruptionConfig {
+    /// Operators the adaptive selector can choose from.
+    /// By default only [`DisruptionOperator::SwapLargeItems`] is enabled, which reproduces the original disruption.
+    /// See [`ALL_DISRUPTION_OPERATORS`](crate::optimizer::disruption::ALL_DISRUPTION_OPERATORS) to enable the full portfolio.
+    pub operators: &'static [DisruptionOperator],
+    /// Weight of the most recent outcome in an operator's score (exponential smoothing)
+    pub reaction_factor: f32,
+    /// Lower bound on the score of an operator, so every operator keeps a chance to be selected
+    pub min_score: f32,
+    /// Width of the slabs, regions and clusters affected by the operators, as a ratio of the strip width
+    pub region_width_ratio: f32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -38,6 +152,57 @@ pub struct CompressionConfig {
     pub time_limit: Duration,
     pub shrink_decay: ShrinkDecayStrategy,
     pub separator_config: SeparatorConfig,
+    /// Where the strip is split in every compression attempt
+    pub split: SplitConfig,
+    /// Gravity compaction pass applied to the final solution of the compression phase, disabled if `None`
+    pub compaction: Option<CompactionConfig>,
+    /// Decomposition of the strip into sliding windows, for very large instances. Disabled if `None`
+    pub window: Option<WindowConfig>,
+}
+
+/// See [`SlidingWindow`](crate::optimizer::window::SlidingWindow)
+#[derive(Debug, Clone, Copy)]
+pub struct WindowConfig {
+    /// Width of a window, as a ratio of the strip width.
+    /// Should be well over twice the width of the largest item, items outside the window are never moved.
+    pub width_ratio: f32,
+    /// Overlap between consecutive windows, as a ratio of the window width
+    pub overlap_ratio: f32,
+    /// Share of the phase's time limit reserved for a global pass at the end, without windows
+    pub global_ratio: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompactionConfig {
+    /// Maximum number of passes over all items
+    pub max_passes: usize,
+    /// Step by which items are slid towards their first contact, relative to the smallest dimension of all items
+    /// (capped by the thinnest feature of any item)
+    pub step_ratio: f32,
+    /// Number of bisections to refine the position of the first contact
+    pub n_bisections: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ShrinkStepStrategy {
+    /// The strip is always shrunk by the same ratio
+    Fixed(f32),
+    /// The shrink ratio grows after quick successes and backs off after repeated failures,
+    /// effectively bisecting between the last feasible and the last failed width
+    Adaptive {
+        /// Initial shrink ratio
+        init: f32,
+        /// Lower bound for the shrink ratio, no more backing off below this value
+        min: f32,
+        /// Upper bound for the shrink ratio
+        max: f32,
+        /// Multiplier applied to the ratio when a width was separated without any failed attempt
+        growth: f32,
+        /// Multiplier applied to the ratio when backing off (0.5 = bisection)
+        backoff: f32,
+        /// Number of consecutive failed attempts at a width before backing off
+        fails_before_backoff: NonZeroUsize,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -48,10 +213,32 @@ pub enum ShrinkDecayStrategy {
     FailureBased(f32),
 }
 
+pub const DEFAULT_GLS_WEIGHT_CONFIG: GLSWeightConfig = GLSWeightConfig {
+    pair_rule: WeightUpdateRule::Multiplicative {
+        min_inc_ratio: GLS_WEIGHT_MIN_INC_RATIO,
+        max_inc_ratio: GLS_WEIGHT_MAX_INC_RATIO,
+        decay: GLS_WEIGHT_DECAY,
+    },
+    container_rule: WeightUpdateRule::Multiplicative {
+        min_inc_ratio: GLS_WEIGHT_MIN_INC_RATIO,
+        max_inc_ratio: GLS_WEIGHT_MAX_INC_RATIO,
+        decay: GLS_WEIGHT_DECAY,
+    },
+    max_weight: None,
+    reset_after_strikes: None,
+    carry_over_decay: None,
+};
+
 pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
     rng_seed: None,
+    constr_cfg: ConstructionConfig {
+        strategies: &[LBF_DEFAULT_STRATEGY],
+        sample_config: LBF_SAMPLE_CONFIG,
+        lattice: None,
+        search_partitions: None,
+    },
     expl_cfg: ExplorationConfig {
-        shrink_step: 0.001,
+        shrink_step: ShrinkStepStrategy::Fixed(0.001),
         time_limit: Duration::from_secs(9 * 60),
         max_conseq_failed_attempts: None,
         solution_pool_distribution_stddev: 0.25,
@@ -63,10 +250,30 @@ pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
             sample_config: SampleConfig {
                 n_container_samples: 50,
                 n_focussed_samples: 25,
+                n_nfp_samples: 0,
                 n_coord_descents: 3,
+                container_sampling: ContainerSampling::Uniform,
+                refiner: RefinerType::CoordinateDescent,
             },
+            weight_config: DEFAULT_GLS_WEIGHT_CONFIG,
+            overlap_quantifier: OverlapQuantifier::PoleProxy,
+        },
+        large_item_ch_area_cutoff_percentile: 0.75,
+        disruption: DisruptionConfig {
+            operators: &[DisruptionOperator::SwapLargeItems],
+            reaction_factor: 0.2,
+            min_score: 0.05,
+            region_width_ratio: 0.1,
         },
-        large_item_ch_area_cutoff_percentile: 0.75
+        split: SplitConfig {
+            strategy: SplitPositionStrategy::Center,
+            n_splits: 1,
+        },
+        ruin_recreate: None,
+        oscillation: None,
+        elite: None,
+        window: None,
+        clustering: None,
     },
     cmpr_cfg: CompressionConfig {
         shrink_range: (0.0005, 0.00001),
@@ -82,9 +289,20 @@ pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
             sample_config: SampleConfig {
                 n_container_samples: 50,
                 n_focussed_samples: 25,
+                n_nfp_samples: 0,
                 n_coord_descents: 3,
+                container_sampling: ContainerSampling::Uniform,
+                refiner: RefinerType::CoordinateDescent,
             },
+            weight_config: DEFAULT_GLS_WEIGHT_CONFIG,
+            overlap_quantifier: OverlapQuantifier::PoleProxy,
+        },
+        split: SplitConfig {
+            strategy: SplitPositionStrategy::Random,
+            n_splits: 1,
         },
+        compaction: None,
+        window: None,
     },
     cde_config: CDEConfig {
         quadtree_depth: 3,
@@ -97,4 +315,5 @@ pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
     },
     poly_simpl_tolerance: Some(0.001),
     min_item_separation: Some(0.4464),
+    symmetry_tolerance: None,
 };
\ No newline at end of file
@@ -1,11 +1,17 @@
 use jagua_rs::io::svg::{SvgDrawOptions, SvgLayoutTheme};
-use crate::sample::search::SampleConfig;
+use crate::optimizer::lbf::{ConstructionStrategy, InitialWidthStrategy, ItemOrdering, PlacementScoring};
+use crate::sample::refine::RefinerType;
+use crate::sample::search::{ContainerSampling, SampleConfig};
 
 pub const GLS_WEIGHT_MAX_INC_RATIO: f32 = 2.0;
 pub const GLS_WEIGHT_MIN_INC_RATIO: f32 = 1.2;
 pub const GLS_WEIGHT_DECAY: f32 = 0.95;
 pub const OVERLAP_PROXY_EPSILON_DIAM_RATIO: f32 = 0.01;
 
+/// Pole pairs which are separated by more than this multiple of epsilon are skipped by the overlap proxy.
+/// Their decayed contribution is at most `epsilon / (2 + ratio)` times the smallest radius.
+pub const OVERLAP_PROXY_PRUNE_EPSILON_RATIO: f32 = 4.0;
+
 
 /// Coordinate descent step multiplier on success
 pub const CD_STEP_SUCCESS: f32 = 1.1;
@@ -28,6 +34,14 @@ pub const SND_REFINE_CD_R_STEPS: (f32, f32) = (f32::to_radians(0.5), f32::to_rad
 /// If two samples are closer than this ratio of the item's min dimension, they are considered duplicates
 pub const UNIQUE_SAMPLE_THRESHOLD: f32 = 0.05;
 
+/// A batch of samples shares a single virtual root of the quadtree if all of them fit in a box
+/// of this multiple of the item's diameter
+pub const BATCH_SHARED_ROOT_DIAM_RATIO: f32 = 2.0;
+
+/// Number of samples evaluated together during the sampling phase of a search,
+/// the bound is only tightened to the best samples found so far in between batches
+pub const SAMPLE_BATCH_SIZE: usize = 8;
+
 pub const DEFAULT_EXPLORE_TIME_RATIO: f32 = 0.8;
 pub const DEFAULT_COMPRESS_TIME_RATIO: f32 = 0.2;
 
@@ -51,5 +65,14 @@ pub const DRAW_OPTIONS: SvgDrawOptions = SvgDrawOptions {
 pub const LBF_SAMPLE_CONFIG: SampleConfig = SampleConfig {
     n_container_samples: 1000,
     n_focussed_samples: 0,
+    n_nfp_samples: 0,
     n_coord_descents: 3,
+    container_sampling: ContainerSampling::Uniform,
+    refiner: RefinerType::CoordinateDescent,
+};
+
+pub const LBF_DEFAULT_STRATEGY: ConstructionStrategy = ConstructionStrategy {
+    ordering: ItemOrdering::ConvexHullAreaDiameter,
+    scoring: PlacementScoring::LeftBottom,
+    width: InitialWidthStrategy::Grow(1.2),
 };
\ No newline at end of file
@@ -1,51 +1,96 @@
 use jagua_rs::collision_detection::hazards::filter::NoFilter;
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::eval::shape_cache::{reference_shape, RotatedShapes};
 use jagua_rs::entities::Item;
 use jagua_rs::entities::Layout;
 use jagua_rs::geometry::DTransformation;
 use jagua_rs::geometry::geo_traits::TransformableFrom;
 use jagua_rs::geometry::primitives::SPolygon;
+use crate::optimizer::lbf::PlacementScoring;
 
 pub const X_MULTIPLIER: f32 = 10.0;
 pub const Y_MULTIPLIER: f32 = 1.0;
 
+/// Distance (relative to the smallest bbox dimension of the item) over which contact with neighbours is probed
+pub const CONTACT_PROBE_RATIO: f32 = 0.01;
+/// Relative loss penalty for every side of the item which is not in contact with anything
+pub const CONTACT_FREE_SIDE_PENALTY: f32 = 0.25;
+
 /// Simple evaluator for the Left-Bottom-Fill constructor.
 /// Basically either returns [SampleEval::Invalid] in case of any collision or [SampleEval::Clear] with a loss value
 /// that rewards placements that are closer to the left-bottom corner of the container.
+/// With [PlacementScoring::MaxContact], placements that are not in contact with anything on some sides are penalized.
 pub struct LBFEvaluator<'a> {
     layout: &'a Layout,
     item: &'a Item,
+    rotated_shapes: Option<&'a RotatedShapes>,
+    scoring: PlacementScoring,
     shape_buff: SPolygon,
+    probe_buff: SPolygon,
     n_evals: usize
 }
 
 impl<'a> LBFEvaluator<'a> {
-    pub fn new(layout: &'a Layout, item: &'a Item) -> Self {
+    pub fn new(layout: &'a Layout, item: &'a Item, scoring: PlacementScoring) -> Self {
         Self {
             layout,
             item,
+            rotated_shapes: None,
+            scoring,
             shape_buff: item.shape_cd.as_ref().clone(),
+            probe_buff: item.shape_cd.as_ref().clone(),
             n_evals: 0
         }
     }
+
+    /// Uses shapes pre-rotated to the item's allowed orientations, so evaluations only need to apply a translation
+    pub fn with_rotated_shapes(mut self, rotated_shapes: Option<&'a RotatedShapes>) -> Self {
+        self.rotated_shapes = rotated_shapes;
+        self
+    }
+
+    /// Counts the sides (left, right, bottom, top) on which the item can be nudged without colliding with anything
+    fn n_free_sides(&mut self, dt: DTransformation) -> usize {
+        let bbox = &self.item.shape_cd.bbox;
+        let delta = CONTACT_PROBE_RATIO * bbox.width().min(bbox.height());
+        let (tx, ty) = dt.translation();
+
+        let mut n_free = 0;
+        for (dx, dy) in [(-delta, 0.0), (delta, 0.0), (0.0, -delta), (0.0, delta)] {
+            let probe_dt = DTransformation::new(dt.rotation(), (tx + dx, ty + dy));
+            let (ref_shape, probe_transf) = reference_shape(self.item, self.rotated_shapes, probe_dt);
+            self.probe_buff.transform_from(ref_shape, &probe_transf);
+            if !self.layout.cde().detect_poly_collision(&self.probe_buff, &NoFilter) {
+                n_free += 1;
+            }
+        }
+        n_free
+    }
 }
 
 impl<'a> SampleEvaluator for LBFEvaluator<'a> {
     fn eval(&mut self, dt: DTransformation, _upper_bound: Option<SampleEval>) -> SampleEval {
         self.n_evals += 1;
         let cde = self.layout.cde();
-        let transf = dt.into();
-        match cde.detect_surrogate_collision(self.item.shape_cd.surrogate(), &transf, &NoFilter) {
+        let (ref_shape, transf) = reference_shape(self.item, self.rotated_shapes, dt);
+        match cde.detect_surrogate_collision(ref_shape.surrogate(), &transf, &NoFilter) {
             true => SampleEval::Invalid, // Surrogate collides with something
             false => {
-                self.shape_buff.transform_from(&self.item.shape_cd, &transf);
+                self.shape_buff.transform_from(ref_shape, &transf);
                 match cde.detect_poly_collision(&self.shape_buff, &NoFilter) {
                     true => SampleEval::Invalid, // Exact shape collides with something
                     false => {
                         // No collisions
                         let poi = self.shape_buff.poi.center;
                         let bbox_corner = self.shape_buff.bbox.corners()[0];
-                        let loss = X_MULTIPLIER * (poi.0 + bbox_corner.0) + Y_MULTIPLIER * (poi.1 + bbox_corner.1);
+                        let lb_loss = X_MULTIPLIER * (poi.0 + bbox_corner.0) + Y_MULTIPLIER * (poi.1 + bbox_corner.1);
+                        let loss = match self.scoring {
+                            PlacementScoring::LeftBottom => lb_loss,
+                            PlacementScoring::MaxContact => {
+                                let n_free_sides = self.n_free_sides(dt);
+                                lb_loss * (1.0 + CONTACT_FREE_SIDE_PENALTY * n_free_sides as f32)
+                            }
+                        };
                         SampleEval::Clear{loss}
                     }
                 }
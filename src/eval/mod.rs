@@ -1,4 +1,7 @@
 pub mod sample_eval;
 pub mod lbf_evaluator;
 pub mod sep_evaluator;
-pub mod specialized_jaguars_pipeline;
\ No newline at end of file
+pub mod specialized_jaguars_pipeline;
+pub mod penalty_evaluator;
+pub mod shape_cache;
+pub mod window_evaluator;
@@ -0,0 +1,62 @@
+use jagua_rs::collision_detection::hazards::collector::{BasicHazardCollector, HazardCollector};
+use jagua_rs::collision_detection::hazards::HazardEntity;
+use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::quantify::{quantify_collision_poly_container, OverlapQuantifier};
+use jagua_rs::entities::Item;
+use jagua_rs::entities::Layout;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::SPolygon;
+
+/// Evaluator for (re)inserting an item which is not part of the layout.
+/// Collisions are allowed, but penalized by their (unweighted) overlap with placed items and the container.
+pub struct PenaltyEvaluator<'a> {
+    layout: &'a Layout,
+    item: &'a Item,
+    quantifier: OverlapQuantifier,
+    shape_buff: SPolygon,
+    n_evals: usize,
+}
+
+impl<'a> PenaltyEvaluator<'a> {
+    pub fn new(layout: &'a Layout, item: &'a Item, quantifier: OverlapQuantifier) -> Self {
+        Self {
+            layout,
+            item,
+            quantifier,
+            shape_buff: item.shape_cd.as_ref().clone(),
+            n_evals: 0,
+        }
+    }
+}
+
+impl<'a> SampleEvaluator for PenaltyEvaluator<'a> {
+    fn eval(&mut self, dt: DTransformation, _upper_bound: Option<SampleEval>) -> SampleEval {
+        self.n_evals += 1;
+        self.shape_buff.transform_from(&self.item.shape_cd, &dt.compose());
+
+        let mut collector = BasicHazardCollector::with_capacity(self.layout.placed_items.len() + 1);
+        self.layout.cde().collect_poly_collisions(&self.shape_buff, &mut collector);
+
+        let loss = collector.iter()
+            .map(|(_, haz)| match haz {
+                HazardEntity::PlacedItem { pk, .. } => {
+                    self.quantifier.quantify_poly_poly(&self.shape_buff, &self.layout.placed_items[*pk].shape)
+                }
+                HazardEntity::Exterior => {
+                    quantify_collision_poly_container(&self.shape_buff, self.layout.container.outer_cd.bbox)
+                }
+                _ => unimplemented!("unsupported hazard entity"),
+            })
+            .sum::<f32>();
+
+        match loss == 0.0 {
+            true => SampleEval::Clear { loss },
+            false => SampleEval::Collision { loss },
+        }
+    }
+
+    fn n_evals(&self) -> usize {
+        self.n_evals
+    }
+}
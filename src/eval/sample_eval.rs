@@ -40,5 +40,26 @@ impl Eq for SampleEval {}
 pub trait SampleEvaluator {
     fn eval(&mut self, dt: DTransformation, upper_bound: Option<SampleEval>) -> SampleEval;
 
+    /// Evaluates a batch of transformations, storing the result of `dts[i]` in `evals[i]`.
+    /// Equivalent to evaluating the samples in order, each one bounded by `upper_bound` and the best evaluation before it
+    /// (see [`tighten_bound`]), so only the best sample of the batch is guaranteed to be evaluated exactly.
+    /// Evaluators can override this to share work between samples, by default they are evaluated one by one.
+    fn eval_many(&mut self, dts: &[DTransformation], upper_bound: Option<SampleEval>, evals: &mut [SampleEval]) {
+        debug_assert!(dts.len() == evals.len());
+        let mut upper_bound = upper_bound;
+        for (dt, eval) in dts.iter().zip(evals.iter_mut()) {
+            *eval = self.eval(*dt, upper_bound);
+            upper_bound = tighten_bound(upper_bound, *eval);
+        }
+    }
+
     fn n_evals(&self) -> usize;
-}
\ No newline at end of file
+}
+
+/// Upper bound for the next sample of a batch, after `eval` was obtained
+pub fn tighten_bound(upper_bound: Option<SampleEval>, eval: SampleEval) -> Option<SampleEval> {
+    match upper_bound {
+        Some(ub) => Some(ub.min(eval)),
+        None => Some(eval),
+    }
+}
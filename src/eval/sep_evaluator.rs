@@ -1,18 +1,24 @@
 use jagua_rs::collision_detection::hazards::collector::HazardCollector;
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
-use crate::eval::specialized_jaguars_pipeline::{collect_poly_collisions_in_detector_custom, SpecializedHazardCollector};
+use crate::eval::shape_cache::{reference_shape, RotatedShapes};
+use crate::eval::specialized_jaguars_pipeline::{collect_poly_collisions_in_detector_at, collect_poly_collisions_in_detector_custom, SpecializedHazardCollector};
+use crate::consts::BATCH_SHARED_ROOT_DIAM_RATIO;
 use crate::quantify::tracker::CollisionTracker;
 use jagua_rs::entities::Item;
 use jagua_rs::entities::Layout;
 use jagua_rs::entities::PItemKey;
 use jagua_rs::geometry::DTransformation;
-use jagua_rs::geometry::primitives::SPolygon;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::{Rect, SPolygon};
 
 pub struct SeparationEvaluator<'a> {
     layout: &'a Layout,
     item: &'a Item,
+    rotated_shapes: Option<&'a RotatedShapes>,
     collector: SpecializedHazardCollector<'a>,
     shape_buff: SPolygon,
+    /// Shape buffers for every sample in a batch
+    batch_buffs: Vec<SPolygon>,
     n_evals: usize,
 }
 
@@ -28,11 +34,19 @@ impl<'a> SeparationEvaluator<'a> {
         Self {
             layout,
             item,
+            rotated_shapes: None,
             collector,
             shape_buff: item.shape_cd.as_ref().clone(),
+            batch_buffs: vec![],
             n_evals: 0,
         }
     }
+
+    /// Uses shapes pre-rotated to the item's allowed orientations, so evaluations only need to apply a translation
+    pub fn with_rotated_shapes(mut self, rotated_shapes: Option<&'a RotatedShapes>) -> Self {
+        self.rotated_shapes = rotated_shapes;
+        self
+    }
 }
 
 impl<'a> SampleEvaluator for SeparationEvaluator<'a> {
@@ -41,28 +55,51 @@ impl<'a> SampleEvaluator for SeparationEvaluator<'a> {
         self.n_evals += 1;
         let cde = self.layout.cde();
 
-        // evals with higher loss than this will always be rejected
-        let loss_bound = match upper_bound {
-            Some(SampleEval::Collision { loss }) => loss,
-            Some(SampleEval::Clear { .. }) => 0.0,
-            _ => f32::INFINITY,
-        };
         // Reload the detection map for the new query and update the loss bound
-        self.collector.reload(loss_bound);
+        self.collector.reload(loss_bound(upper_bound));
 
         // Query the CDE, all colliding hazards will be stored in the detection map
-        collect_poly_collisions_in_detector_custom(cde, &dt, &mut self.shape_buff, self.item.shape_cd.as_ref(), &mut self.collector);
+        let (ref_shape, transf) = reference_shape(self.item, self.rotated_shapes, dt);
+        collect_poly_collisions_in_detector_custom(cde, &transf, &mut self.shape_buff, ref_shape, &mut self.collector);
+
+        collector_eval(&mut self.collector, &self.shape_buff)
+    }
+
+    /// Evaluates a batch of transformations, the loss bound is tightened after every sample.
+    /// If all samples lie close together, they share a single virtual root of the quadtree.
+    fn eval_many(&mut self, dts: &[DTransformation], upper_bound: Option<SampleEval>, evals: &mut [SampleEval]) {
+        debug_assert!(dts.len() == evals.len());
+        self.n_evals += dts.len();
+        let cde = self.layout.cde();
+
+        // Transform all samples up front
+        while self.batch_buffs.len() < dts.len() {
+            self.batch_buffs.push(self.item.shape_cd.as_ref().clone());
+        }
+        for (dt, buff) in dts.iter().zip(self.batch_buffs.iter_mut()) {
+            let (ref_shape, transf) = reference_shape(self.item, self.rotated_shapes, *dt);
+            buff.transform_from(ref_shape, &transf);
+        }
+
+        let batch_bbox = self.batch_buffs[..dts.len()].iter()
+            .map(|s| s.bbox)
+            .reduce(|a, b| Rect {
+                x_min: a.x_min.min(b.x_min),
+                y_min: a.y_min.min(b.y_min),
+                x_max: a.x_max.max(b.x_max),
+                y_max: a.y_max.max(b.y_max),
+            });
+        let max_dim = self.item.shape_cd.diameter * BATCH_SHARED_ROOT_DIAM_RATIO;
+        let v_root = batch_bbox
+            .filter(|bb| bb.width() <= max_dim && bb.height() <= max_dim)
+            .map(|bb| cde.get_virtual_root(bb));
 
-        if self.collector.early_terminate(&self.shape_buff) {
-            //the detection map is in early termination state, this means potentially not all collisions were detected,
-            //but its loss was above the loss bound anyway
-            SampleEval::Invalid
-        } else if self.collector.is_empty() {
-            SampleEval::Clear { loss: 0.0 }
-        } else {
-            SampleEval::Collision {
-                loss: self.collector.loss(&self.shape_buff),
-            }
+        let mut bound = loss_bound(upper_bound);
+        for (shape, eval) in self.batch_buffs.iter().zip(evals.iter_mut()) {
+            self.collector.reload(bound);
+            collect_poly_collisions_in_detector_at(cde, v_root, shape, &mut self.collector);
+            *eval = collector_eval(&mut self.collector, shape);
+            bound = bound.min(loss_bound(Some(*eval)));
         }
     }
 
@@ -71,3 +108,26 @@ impl<'a> SampleEvaluator for SeparationEvaluator<'a> {
     }
 }
 
+/// Evals with higher loss than this will always be rejected
+fn loss_bound(upper_bound: Option<SampleEval>) -> f32 {
+    match upper_bound {
+        Some(SampleEval::Collision { loss }) => loss,
+        Some(SampleEval::Clear { .. }) => 0.0,
+        _ => f32::INFINITY,
+    }
+}
+
+/// Converts the state of the collector after a query into an evaluation
+fn collector_eval(collector: &mut SpecializedHazardCollector, shape: &SPolygon) -> SampleEval {
+    if collector.early_terminate(shape) {
+        //the detection map is in early termination state, this means potentially not all collisions were detected,
+        //but its loss was above the loss bound anyway
+        SampleEval::Invalid
+    } else if collector.is_empty() {
+        SampleEval::Clear { loss: 0.0 }
+    } else {
+        SampleEval::Collision {
+            loss: collector.loss(shape),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use jagua_rs::entities::Item;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::SPolygon;
+use jagua_rs::probs::spp::entities::SPInstance;
+use jagua_rs::geometry::{DTransformation, Transformation};
+
+/// Rotations closer than this (in radians) are considered identical
+const ROTATION_TOLERANCE: f32 = 1e-6;
+
+/// Shapes of an item pre-rotated to each of its allowed orientations.
+/// Only available for items with a discrete set of orientations.
+#[derive(Debug, Clone)]
+pub struct RotatedShapes {
+    entries: Vec<(f32, SPolygon)>,
+}
+
+impl RotatedShapes {
+    pub fn new(item: &Item) -> Option<Self> {
+        let rotations = match &item.allowed_rotation {
+            RotationRange::None => vec![0.0],
+            RotationRange::Discrete(rs) => rs.clone(),
+            RotationRange::Continuous => return None,
+        };
+        let entries = rotations.into_iter()
+            .map(|r| {
+                let mut shape = item.shape_cd.as_ref().clone();
+                shape.transform_from(&item.shape_cd, &Transformation::from_rotation(r));
+                (r, shape)
+            })
+            .collect();
+        Some(Self { entries })
+    }
+
+    pub fn get(&self, rotation: f32) -> Option<&SPolygon> {
+        self.entries.iter()
+            .find(|(r, _)| (r - rotation).abs() < ROTATION_TOLERANCE)
+            .map(|(_, s)| s)
+    }
+}
+
+/// Cache of [`RotatedShapes`] for every item of an instance
+#[derive(Debug, Clone)]
+pub struct ShapeCache {
+    items: Vec<Option<RotatedShapes>>,
+}
+
+impl ShapeCache {
+    pub fn new(instance: &SPInstance) -> Self {
+        let items = instance.items.iter()
+            .map(|(item, _)| RotatedShapes::new(item))
+            .collect();
+        Self { items }
+    }
+
+    pub fn get(&self, item_id: usize) -> Option<&RotatedShapes> {
+        self.items[item_id].as_ref()
+    }
+}
+
+/// Splits `dt` into a reference shape and the transformation that remains to be applied to it.
+/// If the rotation of `dt` is cached, the pre-rotated shape is returned and only a translation remains.
+#[inline(always)]
+pub fn reference_shape<'a>(item: &'a Item, cache: Option<&'a RotatedShapes>, dt: DTransformation) -> (&'a SPolygon, Transformation) {
+    match cache.and_then(|c| c.get(dt.rotation())) {
+        Some(rotated) => (rotated, Transformation::from_translation(dt.translation())),
+        None => (item.shape_cd.as_ref(), dt.compose()),
+    }
+}
@@ -1,7 +1,7 @@
 use std::f32::consts::PI;
-use crate::quantify::quantify_collision_poly_container;
+use crate::quantify::{quantify_collision_poly_container, quantify_collision_poly_poly_exact, OverlapQuantifier};
 #[cfg(not(feature = "simd"))]
-use crate::quantify::quantify_collision_poly_poly;
+use crate::quantify::{quantify_collision_poly_poly, quantify_collision_poly_poly_pruned};
 #[cfg(feature = "simd")]
 use crate::quantify::simd::circles_soa::CirclesSoA;
 #[cfg(feature = "simd")]
@@ -13,10 +13,10 @@ use float_cmp::approx_eq;
 use jagua_rs::collision_detection::CDEngine;
 use jagua_rs::collision_detection::hazards::collector::HazardCollector;
 use jagua_rs::collision_detection::hazards::{HazKey, HazardEntity};
-use jagua_rs::collision_detection::quadtree::QTHazPresence;
+use jagua_rs::collision_detection::quadtree::{QTHazPresence, QTNode};
 use jagua_rs::entities::Layout;
 use jagua_rs::entities::PItemKey;
-use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::{DTransformation, Transformation};
 use jagua_rs::geometry::geo_traits::{TransformableFrom};
 use jagua_rs::geometry::primitives::SPolygon;
 use slotmap::SecondaryMap;
@@ -26,14 +26,24 @@ use slotmap::SecondaryMap;
 /// Saving quite a bit of CPU time since over 90% of the time is spent in this function.
 pub fn collect_poly_collisions_in_detector_custom(
     cde: &CDEngine,
-    dt: &DTransformation,
+    t: &Transformation,
     shape_buffer: &mut SPolygon,
     reference_shape: &SPolygon,
     collector: &mut SpecializedHazardCollector,
 ) {
-    let t = dt.compose();
     // transform the shape buffer to the new position
-    let shape = shape_buffer.transform_from(reference_shape, &t);
+    let shape = shape_buffer.transform_from(reference_shape, t);
+    collect_poly_collisions_in_detector_at(cde, None, shape, collector);
+}
+
+/// Same as [`collect_poly_collisions_in_detector_custom`], but for a shape which is already transformed.
+/// A virtual root of the quadtree containing the shape's bounding box can be provided, to share it between several queries.
+pub fn collect_poly_collisions_in_detector_at(
+    cde: &CDEngine,
+    v_root: Option<&QTNode>,
+    shape: &SPolygon,
+    collector: &mut SpecializedHazardCollector,
+) {
 
     #[cfg(feature = "simd")]
     collector.poles_soa.load(&shape.surrogate().poles);
@@ -55,7 +65,7 @@ pub fn collect_poly_collisions_in_detector_custom(
     }
 
     // Find the virtual root of the quadtree for the shape's bounding box. So we do not have to start from the root every time.
-    let v_quadtree = cde.get_virtual_root(shape.bbox);
+    let v_quadtree = v_root.unwrap_or_else(|| cde.get_virtual_root(shape.bbox));
 
     // Collect collisions for all edges.
     // Iterate over them in a bit-reversed order to maximize detecting new hazards early.
@@ -158,10 +168,17 @@ impl<'a> SpecializedHazardCollector<'a> {
             HazardEntity::PlacedItem { pk: other_pk, .. } => {
                 let other_shape = &self.layout.placed_items[*other_pk].shape;
 
-                #[cfg(not(feature = "simd"))]
-                let loss = quantify_collision_poly_poly(other_shape, shape);
-                #[cfg(feature = "simd")]
-                let loss = quantify_collision_poly_poly_simd(other_shape, shape, &self.poles_soa);
+                let loss = match self.ct.quantifier {
+                    #[cfg(not(feature = "simd"))]
+                    OverlapQuantifier::PoleProxy => quantify_collision_poly_poly(other_shape, shape),
+                    #[cfg(feature = "simd")]
+                    OverlapQuantifier::PoleProxy => quantify_collision_poly_poly_simd(other_shape, shape, &self.poles_soa, false),
+                    #[cfg(not(feature = "simd"))]
+                    OverlapQuantifier::PrunedPoleProxy => quantify_collision_poly_poly_pruned(other_shape, shape),
+                    #[cfg(feature = "simd")]
+                    OverlapQuantifier::PrunedPoleProxy => quantify_collision_poly_poly_simd(other_shape, shape, &self.poles_soa, true),
+                    OverlapQuantifier::ExactArea => quantify_collision_poly_poly_exact(other_shape, shape),
+                };
 
                 let weight = self.ct.get_pair_weight(self.current_pk, *other_pk);
                 loss * weight
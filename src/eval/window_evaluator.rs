@@ -0,0 +1,63 @@
+use crate::eval::sample_eval::{tighten_bound, SampleEval, SampleEvaluator};
+use jagua_rs::entities::Item;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Point;
+
+/// Wraps an evaluator to keep an item inside a horizontal window of the strip.
+/// Samples which would move the centroid of the item outside the window are [`SampleEval::Invalid`].
+/// Without a window, all samples are passed on to the inner evaluator.
+pub struct WindowEvaluator<E: SampleEvaluator> {
+    inner: E,
+    window: Option<(f32, f32)>,
+    /// Centroid of the item in its reference frame
+    centroid: Point,
+    n_evals: usize,
+}
+
+impl<E: SampleEvaluator> WindowEvaluator<E> {
+    pub fn new(inner: E, item: &Item, window: Option<(f32, f32)>) -> Self {
+        Self {
+            inner,
+            window,
+            centroid: item.shape_cd.centroid(),
+            n_evals: 0,
+        }
+    }
+
+    fn in_window(&self, dt: DTransformation) -> bool {
+        self.window.is_none_or(|(x_min, x_max)| {
+            let (sin, cos) = dt.rotation().sin_cos();
+            let x = cos * self.centroid.0 - sin * self.centroid.1 + dt.translation().0;
+            (x_min..x_max).contains(&x)
+        })
+    }
+}
+
+impl<E: SampleEvaluator> SampleEvaluator for WindowEvaluator<E> {
+    fn eval(&mut self, dt: DTransformation, upper_bound: Option<SampleEval>) -> SampleEval {
+        match self.in_window(dt) {
+            true => self.inner.eval(dt, upper_bound),
+            false => {
+                self.n_evals += 1;
+                SampleEval::Invalid
+            }
+        }
+    }
+
+    fn eval_many(&mut self, dts: &[DTransformation], upper_bound: Option<SampleEval>, evals: &mut [SampleEval]) {
+        match self.window {
+            None => self.inner.eval_many(dts, upper_bound, evals),
+            Some(_) => {
+                let mut upper_bound = upper_bound;
+                for (dt, eval) in dts.iter().zip(evals.iter_mut()) {
+                    *eval = self.eval(*dt, upper_bound);
+                    upper_bound = tighten_bound(upper_bound, *eval);
+                }
+            }
+        }
+    }
+
+    fn n_evals(&self) -> usize {
+        self.inner.n_evals() + self.n_evals
+    }
+}
@@ -6,6 +6,7 @@ use rand::prelude::SmallRng;
 use rand::SeedableRng;
 use sparrow::config::*;
 use sparrow::optimizer::optimize;
+use sparrow::util::symmetry::collapse_symmetric_orientations;
 use sparrow::util::io;
 use sparrow::util::io::{MainCli, SPOutput};
 use std::fs;
@@ -89,6 +90,10 @@ fn main() -> Result<()>{
 
     let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
     let instance = jagua_rs::probs::spp::io::import(&importer, &ext_instance)?;
+    let instance = match config.symmetry_tolerance {
+        Some(tolerance) => collapse_symmetric_orientations(&instance, tolerance),
+        None => instance,
+    };
 
     info!("[MAIN] loaded instance {} with #{} items", ext_instance.name, instance.total_item_qty());
     
@@ -114,7 +119,7 @@ fn main() -> Result<()>{
     
     let mut ctrlc_terminator = CtrlCTerminator::new();
 
-    let solution = optimize(instance.clone(), rng, &mut svg_exporter, &mut ctrlc_terminator, &config.expl_cfg, &config.cmpr_cfg);
+    let solution = optimize(instance.clone(), rng, &mut svg_exporter, &mut ctrlc_terminator, &config.constr_cfg, &config.expl_cfg, &config.cmpr_cfg);
 
     let json_path = format!("{OUTPUT_DIR}/final_{}.json", ext_instance.name);
     let json_output = SPOutput {
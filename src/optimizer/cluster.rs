@@ -0,0 +1,265 @@
+use std::cmp::Reverse;
+use std::f32::consts::PI;
+use float_cmp::approx_eq;
+use itertools::Itertools;
+use jagua_rs::entities::{Instance, Item};
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::{Point, SPolygon};
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::{debug, info};
+use ordered_float::OrderedFloat;
+use rand::prelude::SmallRng;
+use rand::{Rng, SeedableRng};
+use crate::config::{ClusterConfig, ConstructionConfig};
+use crate::FMT;
+use crate::optimizer::lbf::construct_best;
+use crate::optimizer::separator::{Separator, SeparatorConfig};
+use crate::sample::uniform_sampler::convert_sample_to_closest_feasible;
+use crate::util::listener::SolutionListener;
+use crate::util::terminator::Terminator;
+use crate::quantify::overlap_area::overlap_area;
+use crate::sample::nfp_sampler::{convex_hull, convex_nfp};
+
+/// Relative rotations of the second part of a pair that are considered
+const PAIR_ROTATIONS: [f32; 4] = [0.0, 0.5 * PI, PI, 1.5 * PI];
+
+/// Overlap (relative to the area of the first part) below which two parts are considered to be merely touching
+const CONTACT_OVERLAP_TOLERANCE: f32 = 1e-6;
+
+/// Instance in which pairs of complementary items are merged into composite items.
+/// The shape of a composite item is the convex hull of its two parts.
+pub struct ClusteredInstance {
+    pub instance: SPInstance,
+    /// For every item of the clustered instance, the original items it consists of,
+    /// together with their transformation relative to the clustered item
+    pub parts: Vec<Vec<(usize, DTransformation)>>,
+}
+
+impl ClusteredInstance {
+    /// Splits all composite items of a solution of the clustered instance back into their parts.
+    /// Returns a problem of the original instance with all parts placed at the same width.
+    /// The parts only inherit the clearance of their composite's convex hull, so the result can contain (slight) collisions,
+    /// see [`ClusteredInstance::split_feasible`].
+    pub fn split(&self, sol: &SPSolution, original: &SPInstance) -> SPProblem {
+        let mut prob = SPProblem::new(original.clone());
+        prob.change_strip_width(sol.strip_width());
+
+        for pi in sol.layout_snapshot.placed_items.values() {
+            let (r, (tx, ty)) = (pi.d_transf.rotation(), pi.d_transf.translation());
+            let (sin, cos) = r.sin_cos();
+            for &(item_id, rel) in self.parts[pi.item_id].iter() {
+                let (rx, ry) = rel.translation();
+                let d_transf = DTransformation::new(
+                    (r + rel.rotation()).rem_euclid(2.0 * PI),
+                    (tx + cos * rx - sin * ry, ty + sin * rx + cos * ry),
+                );
+                //snap to the exact allowed rotation, the composite's rotations only match its parts within a tolerance
+                let d_transf = convert_sample_to_closest_feasible(d_transf, original.item(item_id));
+                prob.place_item(SPPlacement { item_id, d_transf });
+            }
+        }
+        debug!("[CLST] split {} clustered items into {} items", sol.layout_snapshot.placed_items.len(), prob.layout.placed_items.len());
+        prob
+    }
+
+    /// Splits a solution of the clustered instance into a feasible problem of the original instance.
+    /// Collisions between the parts are separated at the same width. If this fails before the terminator
+    /// kills it, a new solution of the original instance is constructed from scratch.
+    pub fn split_feasible(&self, sol: &SPSolution, original: &SPInstance, mut rng: SmallRng, sep_config: SeparatorConfig, constr_config: &ConstructionConfig, term: &impl Terminator, sol_listener: &mut impl SolutionListener) -> SPProblem {
+        let prob = self.split(sol, original);
+        let mut sep = Separator::new(original.clone(), prob, SmallRng::seed_from_u64(rng.random()), sep_config);
+        if sep.ct.get_total_loss() == 0.0 {
+            return sep.prob;
+        }
+
+        info!("[CLST] split solution is infeasible (loss: {}), separating at width {:.3}", FMT().fmt2(sep.ct.get_total_loss()), sep.prob.strip_width());
+        let (sep_sol, sep_ct) = sep.separate(term, sol_listener);
+        if sep_ct.get_total_loss() == 0.0 {
+            sep.prob.restore(&sep_sol);
+            return sep.prob;
+        }
+
+        info!("[CLST] unable to separate the split solution, constructing a new solution of the original instance");
+        construct_best(original, rng, constr_config).prob
+    }
+}
+
+/// Best pairing found between two kinds of items
+struct Pairing {
+    item_ids: (usize, usize),
+    /// Transformation of the second item relative to the first
+    rel: DTransformation,
+    /// Area of both items divided by the area of the convex hull around them
+    utilization: f32,
+    hull: Vec<Point>,
+    allowed_rotation: RotationRange,
+}
+
+/// Searches for pairs of items which fit together densely and merges them into composite items.
+/// Returns `None` if no pair reaches the minimum utilization.
+pub fn cluster_complementary_items(instance: &SPInstance, config: &ClusterConfig) -> Option<ClusteredInstance> {
+    let n_items = instance.items.len();
+    let mut demand = instance.items.iter().map(|(_, qty)| *qty).collect_vec();
+
+    let pairings = (0..n_items)
+        .flat_map(|i| (i..n_items).map(move |j| (i, j)))
+        .filter(|&(i, j)| i != j || demand[i] >= 2)
+        .filter_map(|(i, j)| best_pairing(instance.item(i), instance.item(j), config))
+        .filter(|p| p.utilization >= config.min_utilization)
+        .sorted_by_key(|p| Reverse(OrderedFloat(p.utilization)))
+        .collect_vec();
+
+    let mut items = vec![];
+    let mut parts = vec![];
+
+    //greedily merge as many copies as possible, best pairings first
+    for p in pairings {
+        let (i, j) = p.item_ids;
+        let n_pairs = match i == j {
+            true => demand[i] / 2,
+            false => usize::min(demand[i], demand[j]),
+        };
+        if n_pairs == 0 {
+            continue;
+        }
+        demand[i] -= n_pairs;
+        demand[j] -= n_pairs;
+
+        let id = items.len();
+        let hull_shape = SPolygon::new(p.hull).expect("convex hull should be a valid polygon");
+        let surrogate_config = instance.item(i).shape_cd.surrogate().config;
+        items.push((Item::new(id, hull_shape, p.allowed_rotation, None, surrogate_config), n_pairs));
+        parts.push(vec![(i, DTransformation::new(0.0, (0.0, 0.0))), (j, p.rel)]);
+        info!("[CLST] merging {}x items {} and {} (utilization: {:.3}%)", n_pairs, i, j, p.utilization * 100.0);
+    }
+
+    if items.is_empty() {
+        info!("[CLST] no complementary items found");
+        return None;
+    }
+
+    //all remaining copies stay individual items
+    for (i, (item, _)) in instance.items.iter().enumerate().filter(|(i, _)| demand[*i] > 0) {
+        let id = items.len();
+        //clone the item as is, rebuilding it from its collision shape would simplify and offset it a second time
+        let single = Item { id, ..item.clone() };
+        items.push((single, demand[i]));
+        parts.push(vec![(i, DTransformation::new(0.0, (0.0, 0.0)))]);
+    }
+
+    Some(ClusteredInstance {
+        instance: SPInstance::new(items, instance.base_strip.clone()),
+        parts,
+    })
+}
+
+/// Finds the densest collision-free relative transformation of `b` with respect to `a`, over all relative rotations.
+fn best_pairing(a: &Item, b: &Item, config: &ClusterConfig) -> Option<Pairing> {
+    PAIR_ROTATIONS.iter()
+        .filter_map(|&r| {
+            let allowed_rotation = composite_rotation(&a.allowed_rotation, &b.allowed_rotation, r)?;
+            let (rel, utilization, hull) = densest_contact(&a.shape_cd, &b.shape_cd, r, config.n_contact_samples, config.n_bisections)?;
+            Some(Pairing {
+                item_ids: (a.id, b.id),
+                rel,
+                utilization,
+                hull,
+                allowed_rotation,
+            })
+        })
+        .max_by_key(|p| OrderedFloat(p.utilization))
+}
+
+/// Finds the densest collision-free translation of `b` (rotated by `r`) with respect to the fixed polygon `a`.
+/// `b` starts from positions where both convex hulls touch and is then pushed towards `a` until the (exact) shapes touch.
+/// Returns the transformation of `b`, the utilization of the pair (area of both shapes divided by the area of their convex hull)
+/// and their convex hull.
+pub fn densest_contact(a: &SPolygon, b: &SPolygon, r: f32, n_contact_samples: usize, n_bisections: usize) -> Option<(DTransformation, f32, Vec<Point>)> {
+    let hull_a = convex_hull(a.vertices.clone());
+    let (sin, cos) = r.sin_cos();
+    let hull_b = convex_hull(b.vertices.iter()
+        .map(|p| Point(cos * p.0 - sin * p.1, sin * p.0 + cos * p.1))
+        .collect_vec());
+    let nfp = convex_nfp(&hull_a, &hull_b);
+    let mut b_buff = b.clone();
+
+    let mut best: Option<(DTransformation, f32, Vec<Point>)> = None;
+    let n_samples = n_contact_samples.min(nfp.len());
+    for k in 0..n_samples {
+        let start = nfp[k * nfp.len() / n_samples];
+        //push b towards a, along the line between the start position and the origin
+        let transf_at = |s: f32| DTransformation::new(r, (start.0 * (1.0 - s), start.1 * (1.0 - s)));
+        let collides_at = |s: f32, buff: &mut SPolygon| {
+            buff.transform_from(b, &transf_at(s).compose());
+            overlap_area(a, buff) > a.area * CONTACT_OVERLAP_TOLERANCE
+        };
+        let (mut clear, mut blocked) = (0.0, 1.0);
+        if collides_at(clear, &mut b_buff) {
+            continue;
+        }
+        for _ in 0..n_bisections {
+            let mid = (clear + blocked) / 2.0;
+            match collides_at(mid, &mut b_buff) {
+                true => blocked = mid,
+                false => clear = mid,
+            }
+        }
+
+        let rel = transf_at(clear);
+        b_buff.transform_from(b, &rel.compose());
+        let hull = convex_hull(a.vertices.iter().chain(b_buff.vertices.iter()).copied().collect_vec());
+        let utilization = (a.area + b_buff.area) / polygon_area(&hull);
+
+        if best.as_ref().is_none_or(|(_, u, _)| utilization > *u) {
+            best = Some((rel, utilization, hull));
+        }
+    }
+    best
+}
+
+/// Rotations allowed for a composite with parts `a` and `b`, where `b` is rotated by `r` relative to `a`.
+/// Returns `None` if no rotation of the composite is allowed for both parts.
+fn composite_rotation(a: &RotationRange, b: &RotationRange, r: f32) -> Option<RotationRange> {
+    let rotations_of = |range: &RotationRange| match range {
+        RotationRange::None => Some(vec![0.0]),
+        RotationRange::Discrete(rs) => Some(rs.clone()),
+        RotationRange::Continuous => None,
+    };
+    let same_rotation = |r1: f32, r2: f32| {
+        let diff = (r1 - r2).rem_euclid(2.0 * PI);
+        approx_eq!(f32, diff, 0.0, epsilon = 1e-3) || approx_eq!(f32, diff, 2.0 * PI, epsilon = 1e-3)
+    };
+
+    match (rotations_of(a), rotations_of(b)) {
+        (None, None) => Some(RotationRange::Continuous),
+        (Some(rs_a), rs_b) => {
+            let allowed = rs_a.into_iter()
+                .filter(|ra| rs_b.as_ref().is_none_or(|rs_b| rs_b.iter().any(|rb| same_rotation(ra + r, *rb))))
+                .collect_vec();
+            match allowed.as_slice() {
+                [] => None,
+                [r0] if *r0 == 0.0 => Some(RotationRange::None),
+                _ => Some(RotationRange::Discrete(allowed)),
+            }
+        }
+        (None, Some(rs_b)) => {
+            //a can rotate freely, so the composite can take any rotation that suits b
+            let allowed = rs_b.into_iter().map(|rb| (rb - r).rem_euclid(2.0 * PI)).collect_vec();
+            Some(RotationRange::Discrete(allowed))
+        }
+    }
+}
+
+/// Area of a simple polygon (shoelace formula)
+fn polygon_area(points: &[Point]) -> f32 {
+    let n = points.len();
+    let twice_area = (0..n)
+        .map(|i| {
+            let (p1, p2) = (points[i], points[(i + 1) % n]);
+            p1.0 * p2.1 - p2.0 * p1.1
+        })
+        .sum::<f32>();
+    twice_area.abs() / 2.0
+}
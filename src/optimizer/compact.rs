@@ -0,0 +1,147 @@
+use std::time::Instant;
+use itertools::Itertools;
+use jagua_rs::collision_detection::hazards::filter::NoFilter;
+use jagua_rs::entities::{Instance, Item, Layout};
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::{Point, SPolygon};
+use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use log::{debug, info};
+use ordered_float::OrderedFloat;
+use crate::config::CompactionConfig;
+use crate::util::listener::{ReportType, SolutionListener};
+
+/// Deterministic post-processing pass which slides every item leftward and downward until its first contact
+/// with another item or the container, without introducing any collision.
+/// Repeats until no item moves anymore (or the pass limit is reached), and then fits the strip.
+pub fn compaction_phase(
+    instance: &SPInstance,
+    init: &SPSolution,
+    sol_listener: &mut impl SolutionListener,
+    config: &CompactionConfig,
+) -> SPSolution {
+    let start = Instant::now();
+    let mut prob = SPProblem::new(instance.clone());
+    prob.restore(init);
+
+    let min_item_dim = instance.items.iter()
+        .map(|(item, _)| item.shape_cd.bbox.width().min(item.shape_cd.bbox.height()))
+        .fold(f32::INFINITY, f32::min);
+    //an item may never move further than the thinnest feature in a single step, or it could jump over thin arms
+    let min_feature = instance.items.iter()
+        .map(|(item, _)| thinnest_feature(&item.shape_cd))
+        .fold(f32::INFINITY, f32::min);
+    let step = f32::min(config.step_ratio * min_item_dim, min_feature);
+    debug!("[CMPT] sliding in steps of {:.3} (thinnest feature: {:.3})", step, min_feature);
+    let mut shape_buff = instance.items[0].0.shape_cd.as_ref().clone();
+
+    for pass in 0..config.max_passes {
+        let mut n_moved = 0;
+
+        //handle the items from left-bottom to right-top, so items in front get out of the way first
+        let pks = prob.layout.placed_items.iter()
+            .sorted_by_key(|(_, pi)| (OrderedFloat(pi.shape.bbox.x_min), OrderedFloat(pi.shape.bbox.y_min)))
+            .map(|(pk, _)| pk)
+            .collect_vec();
+
+        for pk in pks {
+            let pi = &prob.layout.placed_items[pk];
+            let (item_id, d_transf) = (pi.item_id, pi.d_transf);
+            let item = instance.item(item_id);
+
+            prob.remove_item(pk);
+            let (d_transf, dist_x) = slide(&prob.layout, item, d_transf, (-1.0, 0.0), step, config.n_bisections, &mut shape_buff);
+            let (d_transf, dist_y) = slide(&prob.layout, item, d_transf, (0.0, -1.0), step, config.n_bisections, &mut shape_buff);
+            prob.place_item(SPPlacement { item_id, d_transf });
+
+            if dist_x > 0.0 || dist_y > 0.0 {
+                n_moved += 1;
+            }
+        }
+        debug!("[CMPT] pass {}: moved {} items", pass, n_moved);
+        if n_moved == 0 {
+            break;
+        }
+    }
+
+    prob.fit_strip();
+    let compacted = prob.save();
+
+    info!("[CMPT] compacted strip width from {:.3} to {:.3} ({:.3}% -> {:.3}%) in {:?}",
+        init.strip_width(), compacted.strip_width(),
+        init.density(instance) * 100.0, compacted.density(instance) * 100.0,
+        start.elapsed()
+    );
+    sol_listener.report(ReportType::CmprFeas, &compacted, instance);
+
+    compacted
+}
+
+/// Slides an item in direction `dir` until its first contact with any hazard.
+/// The item is moved in steps of `step` until it collides, after which the contact is refined by bisection
+/// between the last clear and the first colliding step.
+/// `step` should not exceed the thinnest feature of any shape, otherwise the item can pass through thin arms.
+/// Returns the last collision-free transformation and the distance travelled.
+fn slide(
+    l: &Layout,
+    item: &Item,
+    d_transf: DTransformation,
+    dir: (f32, f32),
+    step: f32,
+    n_bisections: usize,
+    shape_buff: &mut SPolygon,
+) -> (DTransformation, f32) {
+    let rotation = d_transf.rotation();
+    let (tx, ty) = d_transf.translation();
+    let transf_at = |dist: f32| DTransformation::new(rotation, (tx + dir.0 * dist, ty + dir.1 * dist));
+
+    let mut collides_at = |dist: f32| {
+        shape_buff.transform_from(&item.shape_cd, &transf_at(dist).compose());
+        l.cde().detect_poly_collision(shape_buff, &NoFilter)
+    };
+
+    //march until the first collision, the exterior of the container always stops the item eventually
+    let (mut clear, mut blocked) = (0.0, step);
+    while !collides_at(blocked) {
+        clear = blocked;
+        blocked += step;
+    }
+
+    for _ in 0..n_bisections {
+        let mid = (clear + blocked) / 2.0;
+        match collides_at(mid) {
+            true => blocked = mid,
+            false => clear = mid,
+        }
+    }
+
+    (transf_at(clear), clear)
+}
+
+/// Lower bound on the width of the thinnest part of a polygon:
+/// the minimum distance between a vertex and any edge it is not an endpoint of.
+fn thinnest_feature(shape: &SPolygon) -> f32 {
+    let vs = &shape.vertices;
+    let n = vs.len();
+    let mut min_dist = f32::INFINITY;
+    for i in 0..n {
+        for j in 0..n {
+            let k = (j + 1) % n;
+            if i != j && i != k {
+                min_dist = min_dist.min(point_segment_distance(vs[i], vs[j], vs[k]));
+            }
+        }
+    }
+    min_dist
+}
+
+fn point_segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = abx * abx + aby * aby;
+    let t = match len_sq > 0.0 {
+        true => (((p.0 - a.0) * abx + (p.1 - a.1) * aby) / len_sq).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    let (dx, dy) = (p.0 - (a.0 + t * abx), p.1 - (a.1 + t * aby));
+    (dx * dx + dy * dy).sqrt()
+}
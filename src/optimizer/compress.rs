@@ -1,9 +1,10 @@
 use std::time::Instant;
 use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
 use log::info;
-use rand::Rng;
 use crate::config::{CompressionConfig, ShrinkDecayStrategy};
 use crate::optimizer::separator::Separator;
+use crate::optimizer::split::{split_positions, SplitConfig};
+use crate::optimizer::window::SlidingWindow;
 use crate::util::listener::{ReportType, SolutionListener};
 use crate::util::terminator::Terminator;
 
@@ -18,6 +19,7 @@ pub fn compression_phase(
     let mut best = init.clone();
     let start = Instant::now();
     let mut n_failed_attempts = 0;
+    let mut sliding_window = config.window.map(|w| SlidingWindow::new(w, config.time_limit));
 
     let shrink_step_size = |n_failed_attempts: i32| -> f32 {
         match config.shrink_decay {
@@ -38,7 +40,7 @@ pub fn compression_phase(
             break;
         }
         
-        match attempt_to_compress(sep, &best, step, term, sol_listener) {
+        match attempt_to_compress(sep, &best, step, &config.split, sliding_window.as_mut(), term, sol_listener) {
             Some(compacted_sol) => {
                 info!("[CMPR] success at {:.3}% ({:.3} | {:.3}%)", step * 100.0, compacted_sol.strip_width(), compacted_sol.density(instance) * 100.0);
                 sol_listener.report(ReportType::CmprFeas, &compacted_sol, instance);
@@ -50,12 +52,13 @@ pub fn compression_phase(
             }
         }
     }
+    sep.set_window(None);
     info!("[CMPR] finished, compressed from {:.3}% to {:.3}% (+{:.3}%)", init.density(instance) * 100.0, best.density(instance) * 100.0, (best.density(instance) - init.density(instance)) * 100.0);
     best
 }
 
 
-fn attempt_to_compress(sep: &mut Separator, init: &SPSolution, r_shrink: f32, term: &impl Terminator, sol_listener: &mut impl SolutionListener) -> Option<SPSolution> {
+fn attempt_to_compress(sep: &mut Separator, init: &SPSolution, r_shrink: f32, split_config: &SplitConfig, window: Option<&mut SlidingWindow>, term: &impl Terminator, sol_listener: &mut impl SolutionListener) -> Option<SPSolution> {
     // Early termination check
     if term.kill() {
         return None;
@@ -70,10 +73,13 @@ fn attempt_to_compress(sep: &mut Separator, init: &SPSolution, r_shrink: f32, te
         return None;
     }
 
-    //shrink the container at a random position
+    //shrink the container at the configured split position(s)
     let new_width = init.strip_width() * (1.0 - r_shrink);
-    let split_pos = sep.rng.random_range(0.0..sep.prob.strip_width());
-    sep.change_strip_width(new_width, Some(split_pos));
+    let split_positions = match window.and_then(|w| w.advance(sep)) {
+        Some(window_center) => vec![window_center],
+        None => split_positions(&sep.prob.layout, split_config, &mut sep.rng),
+    };
+    sep.change_strip_width_at(new_width, &split_positions);
 
     // Final terminator check before separation
     if term.kill() {
@@ -0,0 +1,433 @@
+use std::cmp::Reverse;
+use std::f32::consts::PI;
+use float_cmp::approx_eq;
+use itertools::Itertools;
+use jagua_rs::collision_detection::hazards::HazardEntity;
+use jagua_rs::entities::{Instance, Layout, PItemKey};
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_traits::CollidesWith;
+use jagua_rs::geometry::primitives::Point;
+use log::{debug, info};
+use ordered_float::OrderedFloat;
+use rand::Rng;
+use rand::prelude::IteratorRandom;
+use slotmap::SecondaryMap;
+use crate::config::{DisruptionConfig, ExplorationConfig};
+use crate::optimizer::separator::Separator;
+use crate::sample::uniform_sampler::{convert_sample_to_closest_feasible, UniformBBoxSampler};
+
+/// Perturbations that can be applied to a layout to escape a local optimum during exploration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisruptionOperator {
+    /// Swaps two 'large' items and carries along the items practically contained by them
+    SwapLargeItems,
+    /// Rotates all items of a vertical slab by 180° around the slab's center
+    RotateSlab,
+    /// Cyclically shifts all items of a vertical slab upwards, wrapping around at the top of the strip
+    TranslateSlab,
+    /// Rotates a cluster of neighbouring items around its center by a multiple of 90°
+    RotateCluster,
+    /// Relocates all items of a vertical slab to random positions in the strip
+    RelocateRegion,
+    /// Relocates the item with the highest GLS weight to a random position in the strip
+    RelocateHeaviest,
+}
+
+pub const ALL_DISRUPTION_OPERATORS: [DisruptionOperator; 6] = [
+    DisruptionOperator::SwapLargeItems,
+    DisruptionOperator::RotateSlab,
+    DisruptionOperator::TranslateSlab,
+    DisruptionOperator::RotateCluster,
+    DisruptionOperator::RelocateRegion,
+    DisruptionOperator::RelocateHeaviest,
+];
+
+/// Disrupts solutions using a portfolio of [`DisruptionOperator`]s.
+/// Operators are selected by roulette wheel, proportional to their recent success.
+pub struct Disruptor {
+    pub config: DisruptionConfig,
+    pub stats: Vec<DisruptionStats>,
+    /// Operator applied last and the loss of the solution it was applied to, awaiting the outcome
+    pending: Option<(usize, f32)>,
+    /// Item id and centroid of the item with the highest GLS weight in the last separated layout
+    heaviest: Option<(usize, Point)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DisruptionStats {
+    pub operator: DisruptionOperator,
+    /// Smoothed reward, used as the operator's share of the roulette wheel
+    pub score: f32,
+    pub n_applied: usize,
+    /// Number of times the separation following the operator reduced the loss
+    pub n_improved: usize,
+    /// Number of times the separation following the operator reached feasibility
+    pub n_feasible: usize,
+}
+
+impl Disruptor {
+    pub fn new(config: DisruptionConfig) -> Self {
+        assert!(!config.operators.is_empty(), "at least one disruption operator should be enabled");
+        assert!(config.min_score > 0.0, "minimum operator score should be strictly positive");
+        let stats = config.operators.iter()
+            .map(|&operator| DisruptionStats {
+                operator,
+                score: 1.0,
+                n_applied: 0,
+                n_improved: 0,
+                n_feasible: 0,
+            })
+            .collect();
+
+        Self {
+            config,
+            stats,
+            pending: None,
+            heaviest: None,
+        }
+    }
+
+    /// Registers the outcome of the separation that followed the last disruption (if any),
+    /// and remembers the item with the highest GLS weight for [`DisruptionOperator::RelocateHeaviest`].
+    pub fn report_separation(&mut self, sep: &Separator, loss: f32) {
+        if let Some((op_idx, loss_before)) = self.pending.take() {
+            let stats = &mut self.stats[op_idx];
+            let reward = if loss == 0.0 {
+                stats.n_feasible += 1;
+                stats.n_improved += 1;
+                1.0
+            } else if loss < loss_before {
+                stats.n_improved += 1;
+                0.5
+            } else {
+                0.0
+            };
+            let rf = self.config.reaction_factor;
+            stats.score = (1.0 - rf) * stats.score + rf * reward;
+            debug!("[DSRP] {:?} outcome: {} -> {} (reward: {}, score: {:.3})", stats.operator, loss_before, loss, reward, stats.score);
+        }
+
+        self.heaviest = sep.prob.layout.placed_items.iter()
+            .max_by_key(|(pk, _)| OrderedFloat(sep.ct.get_excess_weight(*pk)))
+            .map(|(_, pi)| (pi.item_id, pi.shape.centroid()));
+    }
+
+    /// Selects an operator and applies it to the current solution of the separator.
+    /// `loss` is the loss of the solution being disrupted.
+    pub fn disrupt(&mut self, sep: &mut Separator, config: &ExplorationConfig, loss: f32) {
+        let op_idx = self.select(&mut sep.rng);
+        let operator = self.stats[op_idx].operator;
+        self.stats[op_idx].n_applied += 1;
+        self.pending = Some((op_idx, loss));
+
+        match operator {
+            DisruptionOperator::SwapLargeItems => swap_large_items(sep, config),
+            DisruptionOperator::RotateSlab => rotate_slab(sep, self.config.region_width_ratio),
+            DisruptionOperator::TranslateSlab => translate_slab(sep, self.config.region_width_ratio),
+            DisruptionOperator::RotateCluster => rotate_cluster(sep, self.config.region_width_ratio),
+            DisruptionOperator::RelocateRegion => relocate_region(sep, self.config.region_width_ratio),
+            DisruptionOperator::RelocateHeaviest => relocate_heaviest(sep, self.heaviest),
+        }
+    }
+
+    /// Roulette wheel selection, proportional to the (smoothed) success of the operators.
+    /// A single operator is selected without drawing from the rng, so the seeded rng stream is left untouched.
+    pub fn select(&self, rng: &mut impl Rng) -> usize {
+        if self.stats.len() == 1 {
+            return 0;
+        }
+        let scores = self.stats.iter()
+            .map(|s| f32::max(s.score, self.config.min_score))
+            .collect_vec();
+        let total: f32 = scores.iter().sum();
+        let mut pick = rng.random_range(0.0..total);
+        for (i, s) in scores.iter().enumerate() {
+            if pick < *s {
+                return i;
+            }
+            pick -= s;
+        }
+        scores.len() - 1
+    }
+
+    pub fn log_stats(&self) {
+        for s in self.stats.iter() {
+            info!("[DSRP] {:?}: applied {}x, improved {}x, feasible {}x (score: {:.3})", s.operator, s.n_applied, s.n_improved, s.n_feasible, s.score);
+        }
+    }
+}
+
+/// Selects a random vertical slab of the strip, returns the items whose centroid lies within it.
+pub(crate) fn items_in_random_slab(sep: &mut Separator, width_ratio: f32) -> (f32, f32, Vec<PItemKey>) {
+    let strip_width = sep.prob.strip_width();
+    let slab_width = strip_width * width_ratio;
+    let x_min = sep.rng.random_range(0.0..f32::max(strip_width - slab_width, f32::EPSILON));
+    let x_max = x_min + slab_width;
+
+    let pks = sep.prob.layout.placed_items.iter()
+        .filter(|(_, pi)| (x_min..x_max).contains(&pi.shape.centroid().0))
+        .map(|(pk, _)| pk)
+        .collect_vec();
+
+    (x_min, x_max, pks)
+}
+
+/// Rotates a placed item by `angle` around `center`, moving it to the closest feasible transformation.
+fn rotate_item_around(sep: &mut Separator, pk: PItemKey, angle: f32, center: Point) -> PItemKey {
+    let pi = &sep.prob.layout.placed_items[pk];
+    let item = sep.prob.instance.item(pi.item_id);
+    let (tx, ty) = pi.d_transf.translation();
+    let (sin, cos) = angle.sin_cos();
+    let (dx, dy) = (tx - center.0, ty - center.1);
+    let new_dt = DTransformation::new(
+        pi.d_transf.rotation() + angle,
+        (center.0 + cos * dx - sin * dy, center.1 + sin * dx + cos * dy),
+    );
+    let new_dt = convert_sample_to_closest_feasible(new_dt, item);
+    sep.move_item(pk, new_dt)
+}
+
+/// Moves a placed item to a random position (and rotation) in the container.
+fn relocate_item(sep: &mut Separator, pk: PItemKey) {
+    let item_id = sep.prob.layout.placed_items[pk].item_id;
+    let item = sep.prob.instance.item(item_id);
+    let container_bbox = sep.prob.layout.container.outer_cd.bbox;
+    if let Some(sampler) = UniformBBoxSampler::new(container_bbox, item, container_bbox) {
+        let dt = sampler.sample(&mut sep.rng);
+        sep.move_item(pk, dt);
+    }
+}
+
+fn rotate_slab(sep: &mut Separator, width_ratio: f32) {
+    let (x_min, x_max, pks) = items_in_random_slab(sep, width_ratio);
+    let center = Point((x_min + x_max) / 2.0, sep.prob.layout.container.outer_cd.bbox.centroid().1);
+    info!("[EXPL] disrupting by rotating slab [{:.3}, {:.3}] ({} items)", x_min, x_max, pks.len());
+
+    for pk in pks {
+        rotate_item_around(sep, pk, PI, center);
+    }
+}
+
+fn translate_slab(sep: &mut Separator, width_ratio: f32) {
+    let (x_min, x_max, pks) = items_in_random_slab(sep, width_ratio);
+    let container_bbox = sep.prob.layout.container.outer_cd.bbox;
+    let strip_height = container_bbox.height();
+    let dy = sep.rng.random_range(0.0..strip_height);
+    info!("[EXPL] disrupting by translating slab [{:.3}, {:.3}] ({} items) by {:.3}", x_min, x_max, pks.len(), dy);
+
+    for pk in pks {
+        let pi = &sep.prob.layout.placed_items[pk];
+        // wrap items around if they are pushed over the top of the strip
+        let dy = match pi.shape.centroid().1 + dy > container_bbox.y_max {
+            true => dy - strip_height,
+            false => dy,
+        };
+        // keep the item within the strip
+        let dy = dy.clamp(container_bbox.y_min - pi.shape.bbox.y_min, container_bbox.y_max - pi.shape.bbox.y_max);
+        let (tx, ty) = pi.d_transf.translation();
+        let new_dt = DTransformation::new(pi.d_transf.rotation(), (tx, ty + dy));
+        sep.move_item(pk, new_dt);
+    }
+}
+
+fn rotate_cluster(sep: &mut Separator, width_ratio: f32) {
+    let radius = sep.prob.strip_width() * width_ratio / 2.0;
+    let (_, pivot) = sep.prob.layout.placed_items.iter()
+        .choose(&mut sep.rng)
+        .expect("[DSRP] failed to choose pivot item");
+    let center = pivot.shape.centroid();
+
+    let pks = sep.prob.layout.placed_items.iter()
+        .filter(|(_, pi)| {
+            let c = pi.shape.centroid();
+            (c.0 - center.0).powi(2) + (c.1 - center.1).powi(2) <= radius.powi(2)
+        })
+        .map(|(pk, _)| pk)
+        .collect_vec();
+
+    let angle = sep.rng.random_range(1..4) as f32 * PI / 2.0;
+    info!("[EXPL] disrupting by rotating cluster around ({:.3}, {:.3}) ({} items) by {:.0}°", center.0, center.1, pks.len(), angle.to_degrees());
+
+    for pk in pks {
+        rotate_item_around(sep, pk, angle, center);
+    }
+}
+
+fn relocate_region(sep: &mut Separator, width_ratio: f32) {
+    let (x_min, x_max, pks) = items_in_random_slab(sep, width_ratio);
+    info!("[EXPL] disrupting by relocating the items of region [{:.3}, {:.3}] ({} items)", x_min, x_max, pks.len());
+
+    for pk in pks {
+        relocate_item(sep, pk);
+    }
+}
+
+fn relocate_heaviest(sep: &mut Separator, heaviest: Option<(usize, Point)>) {
+    // Find the placed item matching the heaviest item of the last separated layout.
+    // Fall back to a random item if there is none.
+    let target = heaviest.and_then(|(item_id, c)| {
+        sep.prob.layout.placed_items.iter()
+            .filter(|(_, pi)| pi.item_id == item_id)
+            .min_by_key(|(_, pi)| {
+                let pc = pi.shape.centroid();
+                OrderedFloat((pc.0 - c.0).powi(2) + (pc.1 - c.1).powi(2))
+            })
+            .map(|(pk, _)| pk)
+    });
+    let pk = match target {
+        Some(pk) => pk,
+        None => sep.prob.layout.placed_items.keys()
+            .choose(&mut sep.rng)
+            .expect("[DSRP] failed to choose item"),
+    };
+
+    info!("[EXPL] disrupting by relocating the heaviest item (id: {})", sep.prob.layout.placed_items[pk].item_id);
+    relocate_item(sep, pk);
+}
+
+fn swap_large_items(sep: &mut Separator, config: &ExplorationConfig) {
+    // The general idea is to disrupt a solution by swapping two 'large' items in the layout.
+    // 'Large' items are those whose convex hull area falls within a certain top percentile
+    // of the total convex hull area of all items in the layout.
+
+    // Step 1: Define what constitutes a 'large' item.
+
+    // Calculate the total convex hull area of all items, considering quantities.
+    let total_convex_hull_area: f32 = sep
+        .prob
+        .instance
+        .items
+        .iter()
+        .map(|(item, quantity)| item.shape_cd.surrogate().convex_hull_area * (*quantity as f32))
+        .sum();
+
+    let cutoff_threshold_area = total_convex_hull_area * config.large_item_ch_area_cutoff_percentile;
+
+    // Sort items by convex hull area in descending order.
+    let sorted_items_by_ch_area = sep
+        .prob
+        .instance
+        .items
+        .iter()
+        .sorted_by_key(|(item, _)| Reverse(OrderedFloat(item.shape_cd.surrogate().convex_hull_area)))
+        .peekable();
+
+    let mut cumulative_ch_area = 0.0;
+    let mut ch_area_cutoff = 0.0;
+
+    // Iterate through items, accumulating their convex hull areas until the cumulative sum
+    // exceeds the cutoff_threshold_area. The convex hull area of the item that causes
+    // this excess becomes the ch_area_cutoff.
+    for (item, quantity) in sorted_items_by_ch_area {
+        let item_ch_area = item.shape_cd.surrogate().convex_hull_area;
+        cumulative_ch_area += item_ch_area * (*quantity as f32);
+        if cumulative_ch_area > cutoff_threshold_area {
+            ch_area_cutoff = item_ch_area;
+            debug!("[DSRP] cutoff ch area: {}, for item id: {}, bbox: {:?}",ch_area_cutoff, item.id, item.shape_cd.bbox);
+            break;
+        }
+    }
+
+    // Step 2: Select two 'large' items and 'swap' them.
+
+    let large_items = sep.prob.layout.placed_items.iter()
+        .filter(|(_, pi)| pi.shape.surrogate().convex_hull_area >= ch_area_cutoff);
+
+    //Choose a first item with a large enough convex hull
+    let (pk1, pi1) = large_items.clone().choose(&mut sep.rng).expect("[DSRP] failed to choose first item");
+
+    //Choose a second item with a large enough convex hull and different enough from the first.
+    //If no such item is found, choose a random one.
+    let (pk2, pi2) = large_items.clone()
+        .filter(|(_, pi)|
+            // Ensure the second item is different from the first
+            !approx_eq!(f32, pi.shape.area,pi1.shape.area, epsilon = pi1.shape.area * 0.01) &&
+                !approx_eq!(f32, pi.shape.diameter, pi1.shape.diameter, epsilon = pi1.shape.diameter * 0.01)
+        )
+        .choose(&mut sep.rng)
+        .or_else(|| {
+            sep.prob.layout.placed_items.iter()
+                .filter(|(pk, _)| *pk != pk1) // Ensure the second item is not the same as the first
+                .choose(&mut sep.rng)
+        }) // As a fallback, choose any item
+        .expect("[EXPL] failed to choose second item for disruption");
+
+    // Step 3: Swap the two items' positions in the layout.
+
+    let dt1_old = pi1.d_transf;
+    let dt2_old = pi2.d_transf;
+
+    // Make sure the swaps do not violate feasibility (rotation).
+    let dt1_new = convert_sample_to_closest_feasible(dt2_old, sep.prob.instance.item(pi1.item_id));
+    let dt2_new = convert_sample_to_closest_feasible(dt1_old, sep.prob.instance.item(pi2.item_id));
+
+    info!("[EXPL] disrupting by swapping two large items (id: {} <-> {})", pi1.item_id, pi2.item_id);
+
+    let pk1 = sep.move_item(pk1, dt1_new);
+    let pk2 = sep.move_item(pk2, dt2_new);
+
+
+    // Step 4: Move all items that are practically contained by one of the swapped items to the "empty space" created by the moved item.
+    //         This is particularly important when huge items are swapped with smaller items. 
+    //         The huge item will create a large empty space and many of the items which previously 
+    //         surrounded the smaller one will be contained by the huge one.
+    {
+        // transformation to convert the contained items' position (relative to the old and new positions of the swapped items)
+        let converting_transformation = dt1_new.compose().inverse()
+            .transform(&dt1_old.compose());
+
+        for c1_pk in practically_contained_items(&sep.prob.layout, pk1).into_iter().filter(|c1_pk| *c1_pk != pk2) {
+            let c1_pi = &sep.prob.layout.placed_items[c1_pk];
+
+            let new_dt = c1_pi.d_transf
+                .compose()
+                .transform(&converting_transformation)
+                .decompose();
+
+            //Ensure the sure the new position is feasible
+            let new_feasible_dt = convert_sample_to_closest_feasible(new_dt, sep.prob.instance.item(c1_pi.item_id));
+            sep.move_item(c1_pk, new_feasible_dt);
+        }
+    }
+
+    // Do the same for the second item, but using the second transformation
+    {
+        let converting_transformation = dt2_new.compose().inverse()
+            .transform(&dt2_old.compose());
+
+        for c2_pk in practically_contained_items(&sep.prob.layout, pk2).into_iter().filter(|c2_pk| *c2_pk != pk1) {
+            let c2_pi = &sep.prob.layout.placed_items[c2_pk];
+            let new_dt = c2_pi.d_transf
+                .compose()
+                .transform(&converting_transformation)
+                .decompose();
+
+            //make sure the new position is feasible
+            let new_feasible_dt = convert_sample_to_closest_feasible(new_dt, sep.prob.instance.item(c2_pi.item_id));
+            sep.move_item(c2_pk, new_feasible_dt);
+        }
+    }
+}
+
+/// Collects all items which point of inaccessibility (POI) is contained by pk_c's shape.
+fn practically_contained_items(layout: &Layout, pk_c: PItemKey) -> Vec<PItemKey> {
+    let pi_c = &layout.placed_items[pk_c];
+    // Detect all collisions with the item pk_c's shape.
+    let mut collector = SecondaryMap::new();
+    layout.cde().collect_poly_collisions(&pi_c.shape, &mut collector);
+
+    // Filter out the items that have their POI contained by pk_c's shape.
+    collector.iter()
+        .filter_map(|(_,he)| {
+            match he {
+                HazardEntity::PlacedItem { pk, .. } => Some(*pk),
+                _ => None
+            }
+        })
+        .filter(|pk| *pk != pk_c) // Ensure we don't include the item itself
+        .filter(|pk| {
+            // Check if the POI of the item is contained by pk_c's shape
+            let poi = layout.placed_items[*pk].shape.poi;
+            pi_c.shape.collides_with(&poi.center)
+        })
+        .collect_vec()
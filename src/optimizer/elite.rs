@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+use jagua_rs::entities::PItemKey;
+use jagua_rs::geometry::primitives::Point;
+use jagua_rs::probs::spp::entities::SPSolution;
+use log::{debug, info};
+use ordered_float::OrderedFloat;
+use rand::Rng;
+use rand::seq::index::sample;
+use crate::config::EliteConfig;
+use crate::optimizer::separator::Separator;
+
+/// Persistent set of diverse feasible solutions found during the exploration phase.
+/// Solutions are kept distinct by [`layout_distance`]: a new solution too close to an existing member can only replace it.
+pub struct ElitePool {
+    pub config: EliteConfig,
+    pub members: Vec<SPSolution>,
+}
+
+impl ElitePool {
+    pub fn new(config: EliteConfig) -> Self {
+        assert!(config.capacity >= 2, "elite pool should be able to hold at least two solutions");
+        Self {
+            config,
+            members: vec![],
+        }
+    }
+
+    /// Offers a feasible solution to the pool, returns whether it was admitted
+    pub fn insert(&mut self, sol: &SPSolution) -> bool {
+        let closest = self.members.iter()
+            .enumerate()
+            .map(|(i, m)| (i, layout_distance(sol, m)))
+            .min_by_key(|(_, d)| OrderedFloat(*d));
+
+        match closest {
+            Some((i, d)) if d < self.config.min_distance => {
+                //too similar to an existing member, only replace it if narrower
+                if sol.strip_width() < self.members[i].strip_width() {
+                    debug!("[ELIT] replacing member {} (dist: {:.3}, width: {:.3} -> {:.3})", i, d, self.members[i].strip_width(), sol.strip_width());
+                    self.members[i] = sol.clone();
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => {
+                self.members.push(sol.clone());
+                if self.members.len() > self.config.capacity {
+                    //evict the widest member
+                    let (widest, _) = self.members.iter()
+                        .enumerate()
+                        .max_by_key(|(_, m)| OrderedFloat(m.strip_width()))
+                        .unwrap();
+                    self.members.swap_remove(widest);
+                }
+                debug!("[ELIT] admitted solution with width {:.3} ({} members)", sol.strip_width(), self.members.len());
+                true
+            }
+        }
+    }
+
+    /// Whether a recombination should replace the disruption after `n_conseq_fails` failed attempts
+    pub fn recombination_due(&self, n_conseq_fails: usize) -> bool {
+        self.members.len() >= 2 && n_conseq_fails % self.config.recombine_after_fails.get() == 0
+    }
+
+    /// Recombines two random members into the separator at its current width:
+    /// a random vertical region is copied from one member, the rest is taken from the other.
+    /// The resulting layout generally contains collisions, which are left to be repaired by the separator.
+    pub fn recombine(&self, sep: &mut Separator) {
+        let idxs = sample(&mut sep.rng, self.members.len(), 2);
+        let (donor, base) = (&self.members[idxs.index(0)], &self.members[idxs.index(1)]);
+        let target_width = sep.prob.strip_width();
+
+        //start from the base member, fitted into the current width
+        sep.change_strip_width(base.strip_width(), None);
+        sep.rollback(base, None);
+        sep.change_strip_width(target_width, None);
+
+        let region_width = target_width * self.config.region_width_ratio;
+        let x_min = sep.rng.random_range(0.0..f32::max(target_width - region_width, f32::EPSILON));
+        let x_max = x_min + region_width;
+        let in_region = |p: Point| (x_min..x_max).contains(&p.0);
+
+        let donor_placements = donor.layout_snapshot.placed_items.values()
+            .filter(|pi| in_region(pi.shape.centroid()))
+            .map(|pi| (pi.item_id, pi.d_transf, pi.shape.centroid()))
+            .collect_vec();
+
+        //move the corresponding items of the base to the placements of the donor,
+        //preferring items of the same kind which already lie in the region
+        let mut moved: Vec<PItemKey> = vec![];
+        for (item_id, d_transf, centroid) in donor_placements {
+            let candidate = sep.prob.layout.placed_items.iter()
+                .filter(|(pk, pi)| pi.item_id == item_id && !moved.contains(pk))
+                .min_by_key(|(_, pi)| {
+                    let c = pi.shape.centroid();
+                    (!in_region(c), OrderedFloat(sq_distance(c, centroid)))
+                })
+                .map(|(pk, _)| pk);
+
+            if let Some(pk) = candidate {
+                moved.push(sep.move_item(pk, d_transf));
+            }
+        }
+
+        info!("[EXPL] recombined region [{:.3}, {:.3}] of elite (w: {:.3}) into elite (w: {:.3}), {} items moved",
+            x_min, x_max, donor.strip_width(), base.strip_width(), moved.len());
+    }
+}
+
+/// Distance between two layouts based on the positions of their items, relative to the height of the strip.
+/// For every item, the placements in both layouts are matched greedily by increasing distance (closest pairs first),
+/// the distance is the average distance between matched placements.
+/// The matching does not depend on the order in which the placements are stored.
+pub fn layout_distance(a: &SPSolution, b: &SPSolution) -> f32 {
+    let centroids_per_item = |sol: &SPSolution| {
+        let mut positions: HashMap<usize, Vec<Point>> = HashMap::new();
+        for pi in sol.layout_snapshot.placed_items.values() {
+            positions.entry(pi.item_id).or_default().push(pi.shape.centroid());
+        }
+        positions
+    };
+    let (a_positions, b_positions) = (centroids_per_item(a), centroids_per_item(b));
+
+    let mut total_dist = 0.0;
+    let mut n_matched = 0;
+    for (item_id, a_cs) in a_positions.iter() {
+        let Some(b_cs) = b_positions.get(item_id) else { continue };
+        //sorted by position and ties broken by index, so the storage order of the placements is irrelevant
+        let a_cs = a_cs.iter().sorted_by_key(|p| point_key(**p)).collect_vec();
+        let b_cs = b_cs.iter().sorted_by_key(|p| point_key(**p)).collect_vec();
+        let pairs = (0..a_cs.len()).cartesian_product(0..b_cs.len())
+            .map(|(i, j)| (OrderedFloat(sq_distance(*a_cs[i], *b_cs[j])), i, j))
+            .sorted()
+            .collect_vec();
+
+        let (mut a_matched, mut b_matched) = (vec![false; a_cs.len()], vec![false; b_cs.len()]);
+        for (d, i, j) in pairs {
+            if !a_matched[i] && !b_matched[j] {
+                total_dist += d.0.sqrt();
+                n_matched += 1;
+                a_matched[i] = true;
+                b_matched[j] = true;
+            }
+        }
+    }
+
+    let height = a.layout_snapshot.container.outer_cd.bbox.height();
+    match n_matched {
+        0 => f32::INFINITY,
+        _ => total_dist / (n_matched as f32 * height),
+    }
+}
+
+/// Totally ordered key of a position
+fn point_key(p: Point) -> (OrderedFloat<f32>, OrderedFloat<f32>) {
+    (OrderedFloat(p.0), OrderedFloat(p.1))
+}
+
+fn sq_distance(p1: Point, p2: Point) -> f32 {
+    (p1.0 - p2.0).powi(2) + (p1.1 - p2.1).powi(2)
+}
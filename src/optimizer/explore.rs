@@ -1,19 +1,16 @@
-use std::cmp::Reverse;
-use float_cmp::approx_eq;
-use itertools::Itertools;
-use jagua_rs::collision_detection::hazards::HazardEntity;
-use jagua_rs::entities::{Instance, Layout, PItemKey};
-use jagua_rs::geometry::geo_traits::CollidesWith;
 use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
-use log::{debug, info};
-use ordered_float::OrderedFloat;
-use rand::prelude::{Distribution, IteratorRandom};
+use log::info;
+use rand::prelude::Distribution;
 use rand_distr::Normal;
-use slotmap::SecondaryMap;
 use crate::config::ExplorationConfig;
 use crate::FMT;
+use crate::optimizer::disruption::Disruptor;
+use crate::optimizer::elite::ElitePool;
 use crate::optimizer::separator::{Separator, SeparatorConfig};
-use crate::sample::uniform_sampler::convert_sample_to_closest_feasible;
+use crate::optimizer::ruin_recreate::ruin_and_recreate;
+use crate::optimizer::shrink_schedule::ShrinkSchedule;
+use crate::optimizer::split::split_positions;
+use crate::optimizer::window::SlidingWindow;
 use crate::util::listener::{ReportType, SolutionListener};
 use crate::util::terminator::Terminator;
 
@@ -27,10 +24,21 @@ pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, sol_listene
     info!("[EXPL] starting optimization with initial width: {:.3} ({:.3}%)",current_width,sep.prob.density() * 100.0);
 
     let mut solution_pool: Vec<(SPSolution, f32)> = vec![];
+    //failed attempts since the last feasible solution, unlike the solution pool this is not reset by a backoff
+    let mut n_conseq_fails = 0;
+    let mut shrink_schedule = ShrinkSchedule::new(config.shrink_step);
+    let mut disruptor = Disruptor::new(config.disruption);
+    let mut n_oscillations = 0;
+    let mut elite_pool = config.elite.map(ElitePool::new);
+    let mut sliding_window = config.window.map(|w| SlidingWindow::new(w, config.time_limit));
+    if let Some(elite_pool) = elite_pool.as_mut() {
+        elite_pool.insert(&feasible_solutions[0]);
+    }
 
     while !term.kill() {
         let local_best = sep.separate(term, sol_listener);
         let total_loss = local_best.1.get_total_loss();
+        disruptor.report_separation(sep, total_loss);
 
         if total_loss == 0.0 {
             //layout is successfully separated
@@ -40,11 +48,20 @@ pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, sol_listene
                 feasible_solutions.push(local_best.0.clone());
                 sol_listener.report(ReportType::ExplFeas, &local_best.0, instance);
             }
-            let next_width = current_width * (1.0 - config.shrink_step);
-            info!("[EXPL] shrinking strip by {}%: {:.3} -> {:.3}", config.shrink_step * 100.0, current_width, next_width);
-            sep.change_strip_width(next_width, None);
+            if let Some(elite_pool) = elite_pool.as_mut() {
+                elite_pool.insert(&local_best.0);
+            }
+            let shrink_step = shrink_schedule.report_success();
+            let next_width = current_width * (1.0 - shrink_step);
+            info!("[EXPL] shrinking strip by {}%: {:.3} -> {:.3}", shrink_step * 100.0, current_width, next_width);
+            let split_positions = match sliding_window.as_mut().and_then(|w| w.advance(sep)) {
+                Some(window_center) => vec![window_center],
+                None => split_positions(&sep.prob.layout, &config.split, &mut sep.rng),
+            };
+            sep.change_strip_width_at(next_width, &split_positions);
             current_width = next_width;
             solution_pool.clear();
+            n_conseq_fails = 0;
         } else {
             info!("[EXPL] unable to reach feasibility (width: {:.3}, dens: {:.3}%, min loss: {:.3})", current_width, sep.prob.density() * 100.0, FMT().fmt2(total_loss));
             sol_listener.report(ReportType::ExplInfeas, &local_best.0, instance);
@@ -54,13 +71,55 @@ pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, sol_listene
                 Ok(idx) | Err(idx) => solution_pool.insert(idx, (local_best.0.clone(), total_loss)),
             }
 
-            if solution_pool.len() >= config.max_conseq_failed_attempts.unwrap_or(usize::MAX) {
-                info!("[EXPL] max consecutive failed attempts ({}), terminating", solution_pool.len());
+            n_conseq_fails += 1;
+            if n_conseq_fails >= config.max_conseq_failed_attempts.unwrap_or(usize::MAX) {
+                info!("[EXPL] max consecutive failed attempts ({}), terminating", n_conseq_fails);
                 break;
             }
 
+            if let Some(shrink_step) = shrink_schedule.report_failure() {
+                //back off: restart from the best feasible solution with a smaller shrink step
+                let best_feasible = feasible_solutions.last().unwrap();
+                let next_width = best_width * (1.0 - shrink_step);
+                info!("[EXPL] backing off, shrinking best feasible solution by {}%: {:.3} -> {:.3}", shrink_step * 100.0, best_width, next_width);
+                sep.change_strip_width(best_width, None);
+                sep.rollback(best_feasible, None);
+                let split_positions = match sliding_window.as_mut().and_then(|w| w.advance(sep)) {
+                    Some(window_center) => vec![window_center],
+                    None => split_positions(&sep.prob.layout, &config.split, &mut sep.rng),
+                };
+                sep.change_strip_width_at(next_width, &split_positions);
+                current_width = next_width;
+                solution_pool.clear();
+                continue;
+            }
+
+            if let Some(osc_config) = config.oscillation
+                && n_oscillations < osc_config.max_oscillations
+                && n_conseq_fails % osc_config.trigger_after_fails.get() == 0 {
+                //strategic oscillation: widen the strip above the best feasible width and shrink again from there
+                let (least_loss_sol, _) = &solution_pool[0];
+                let next_width = best_width * (1.0 + osc_config.widen_ratio);
+                n_oscillations += 1;
+                info!("[EXPL] oscillating ({}/{}), widening least infeasible solution: {:.3} -> {:.3}", n_oscillations, osc_config.max_oscillations, current_width, next_width);
+                sep.rollback(least_loss_sol, None);
+                let split_positions = split_positions(&sep.prob.layout, &config.split, &mut sep.rng);
+                sep.change_strip_width_at(next_width, &split_positions);
+                current_width = next_width;
+                solution_pool.clear();
+                continue;
+            }
+
+            //the moves below act on the whole strip, so the next separation cannot be restricted to a window
+            sep.lift_window();
+
+            if let Some(elite_pool) = elite_pool.as_ref() && elite_pool.recombination_due(solution_pool.len()) {
+                elite_pool.recombine(sep);
+                continue;
+            }
+
             //restore to a random solution from the tabu list, better solutions have more chance to be selected
-            let selected_sol = {
+            let (selected_sol, selected_loss) = {
                 //sample a value in range [0.0, 1.0[ from a normal distribution
                 let distr = Normal::new(0.0, config.solution_pool_distribution_stddev).unwrap();
                 let sample = distr.sample(&mut sep.rng).abs().min(0.999);
@@ -69,163 +128,22 @@ pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, sol_listene
 
                 let (selected_sol, loss) = &solution_pool[selected_idx];
                 info!("[EXPL] starting solution {}/{} selected from solution pool (l: {}) to disrupt", selected_idx, solution_pool.len(), FMT().fmt2(*loss));
-                selected_sol
+                (selected_sol, *loss)
             };
 
             sep.rollback(selected_sol, None);
-            disrupt_solution(sep, config);
+            match config.ruin_recreate {
+                Some(rr_config) if solution_pool.len() % rr_config.trigger_after_fails.get() == 0 => {
+                    ruin_and_recreate(sep, &rr_config);
+                }
+                _ => disruptor.disrupt(sep, config, selected_loss),
+            }
         }
     }
 
+    disruptor.log_stats();
+    sep.set_window(None);
     info!("[EXPL] finished, best feasible solution: width: {:.3} ({:.3}%)",best_width,feasible_solutions.last().unwrap().density(instance) * 100.0);
 
     feasible_solutions
 }
-
-fn disrupt_solution(sep: &mut Separator, config: &ExplorationConfig) {
-    // The general idea is to disrupt a solution by swapping two 'large' items in the layout.
-    // 'Large' items are those whose convex hull area falls within a certain top percentile
-    // of the total convex hull area of all items in the layout.
-
-    // Step 1: Define what constitutes a 'large' item.
-
-    // Calculate the total convex hull area of all items, considering quantities.
-    let total_convex_hull_area: f32 = sep
-        .prob
-        .instance
-        .items
-        .iter()
-        .map(|(item, quantity)| item.shape_cd.surrogate().convex_hull_area * (*quantity as f32))
-        .sum();
-
-    let cutoff_threshold_area = total_convex_hull_area * config.large_item_ch_area_cutoff_percentile;
-
-    // Sort items by convex hull area in descending order.
-    let sorted_items_by_ch_area = sep
-        .prob
-        .instance
-        .items
-        .iter()
-        .sorted_by_key(|(item, _)| Reverse(OrderedFloat(item.shape_cd.surrogate().convex_hull_area)))
-        .peekable();
-
-    let mut cumulative_ch_area = 0.0;
-    let mut ch_area_cutoff = 0.0;
-
-    // Iterate through items, accumulating their convex hull areas until the cumulative sum
-    // exceeds the cutoff_threshold_area. The convex hull area of the item that causes
-    // this excess becomes the ch_area_cutoff.
-    for (item, quantity) in sorted_items_by_ch_area {
-        let item_ch_area = item.shape_cd.surrogate().convex_hull_area;
-        cumulative_ch_area += item_ch_area * (*quantity as f32);
-        if cumulative_ch_area > cutoff_threshold_area {
-            ch_area_cutoff = item_ch_area;
-            debug!("[DSRP] cutoff ch area: {}, for item id: {}, bbox: {:?}",ch_area_cutoff, item.id, item.shape_cd.bbox);
-            break;
-        }
-    }
-
-    // Step 2: Select two 'large' items and 'swap' them.
-
-    let large_items = sep.prob.layout.placed_items.iter()
-        .filter(|(_, pi)| pi.shape.surrogate().convex_hull_area >= ch_area_cutoff);
-
-    //Choose a first item with a large enough convex hull
-    let (pk1, pi1) = large_items.clone().choose(&mut sep.rng).expect("[DSRP] failed to choose first item");
-
-    //Choose a second item with a large enough convex hull and different enough from the first.
-    //If no such item is found, choose a random one.
-    let (pk2, pi2) = large_items.clone()
-        .filter(|(_, pi)|
-            // Ensure the second item is different from the first
-            !approx_eq!(f32, pi.shape.area,pi1.shape.area, epsilon = pi1.shape.area * 0.01) &&
-                !approx_eq!(f32, pi.shape.diameter, pi1.shape.diameter, epsilon = pi1.shape.diameter * 0.01)
-        )
-        .choose(&mut sep.rng)
-        .or_else(|| {
-            sep.prob.layout.placed_items.iter()
-                .filter(|(pk, _)| *pk != pk1) // Ensure the second item is not the same as the first
-                .choose(&mut sep.rng)
-        }) // As a fallback, choose any item
-        .expect("[EXPL] failed to choose second item for disruption");
-
-    // Step 3: Swap the two items' positions in the layout.
-
-    let dt1_old = pi1.d_transf;
-    let dt2_old = pi2.d_transf;
-
-    // Make sure the swaps do not violate feasibility (rotation).
-    let dt1_new = convert_sample_to_closest_feasible(dt2_old, sep.prob.instance.item(pi1.item_id));
-    let dt2_new = convert_sample_to_closest_feasible(dt1_old, sep.prob.instance.item(pi2.item_id));
-
-    info!("[EXPL] disrupting by swapping two large items (id: {} <-> {})", pi1.item_id, pi2.item_id);
-
-    let pk1 = sep.move_item(pk1, dt1_new);
-    let pk2 = sep.move_item(pk2, dt2_new);
-
-
-    // Step 4: Move all items that are practically contained by one of the swapped items to the "empty space" created by the moved item.
-    //         This is particularly important when huge items are swapped with smaller items. 
-    //         The huge item will create a large empty space and many of the items which previously 
-    //         surrounded the smaller one will be contained by the huge one.
-    {
-        // transformation to convert the contained items' position (relative to the old and new positions of the swapped items)
-        let converting_transformation = dt1_new.compose().inverse()
-            .transform(&dt1_old.compose());
-
-        for c1_pk in practically_contained_items(&sep.prob.layout, pk1).into_iter().filter(|c1_pk| *c1_pk != pk2) {
-            let c1_pi = &sep.prob.layout.placed_items[c1_pk];
-
-            let new_dt = c1_pi.d_transf
-                .compose()
-                .transform(&converting_transformation)
-                .decompose();
-
-            //Ensure the sure the new position is feasible
-            let new_feasible_dt = convert_sample_to_closest_feasible(new_dt, sep.prob.instance.item(c1_pi.item_id));
-            sep.move_item(c1_pk, new_feasible_dt);
-        }
-    }
-
-    // Do the same for the second item, but using the second transformation
-    {
-        let converting_transformation = dt2_new.compose().inverse()
-            .transform(&dt2_old.compose());
-
-        for c2_pk in practically_contained_items(&sep.prob.layout, pk2).into_iter().filter(|c2_pk| *c2_pk != pk1) {
-            let c2_pi = &sep.prob.layout.placed_items[c2_pk];
-            let new_dt = c2_pi.d_transf
-                .compose()
-                .transform(&converting_transformation)
-                .decompose();
-
-            //make sure the new position is feasible
-            let new_feasible_dt = convert_sample_to_closest_feasible(new_dt, sep.prob.instance.item(c2_pi.item_id));
-            sep.move_item(c2_pk, new_feasible_dt);
-        }
-    }
-}
-
-/// Collects all items which point of inaccessibility (POI) is contained by pk_c's shape.
-fn practically_contained_items(layout: &Layout, pk_c: PItemKey) -> Vec<PItemKey> {
-    let pi_c = &layout.placed_items[pk_c];
-    // Detect all collisions with the item pk_c's shape.
-    let mut collector = SecondaryMap::new();
-    layout.cde().collect_poly_collisions(&pi_c.shape, &mut collector);
-
-    // Filter out the items that have their POI contained by pk_c's shape.
-    collector.iter()
-        .filter_map(|(_,he)| {
-            match he {
-                HazardEntity::PlacedItem { pk, .. } => Some(*pk),
-                _ => None
-            }
-        })
-        .filter(|pk| *pk != pk_c) // Ensure we don't include the item itself
-        .filter(|pk| {
-            // Check if the POI of the item is contained by pk_c's shape
-            let poi = layout.placed_items[*pk].shape.poi;
-            pi_c.shape.collides_with(&poi.center)
-        })
-        .collect_vec()
-}
\ No newline at end of file
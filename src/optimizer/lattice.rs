@@ -0,0 +1,191 @@
+use std::f32::consts::PI;
+use float_cmp::approx_eq;
+use itertools::Itertools;
+use jagua_rs::collision_detection::hazards::filter::NoFilter;
+use jagua_rs::entities::Item;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::SPolygon;
+use jagua_rs::probs::spp::entities::{SPPlacement, SPProblem};
+use log::debug;
+use ordered_float::OrderedFloat;
+use crate::config::LatticeConfig;
+use crate::optimizer::cluster::densest_contact;
+use crate::quantify::overlap_area::overlap_area;
+
+/// Overlap (relative to the area of the item) below which copies are considered to be merely touching
+const LATTICE_OVERLAP_TOLERANCE: f32 = 1e-6;
+
+/// Periodic packing of copies of a single item.
+/// A motif of one item (single lattice) or two items rotated by 180° relative to each other (double lattice)
+/// is repeated at every point `i * (dx, 0) + j * (shear, dy)`.
+#[derive(Debug, Clone)]
+pub struct Lattice {
+    pub item_id: usize,
+    /// Transformations of the items in the motif
+    pub motif: Vec<DTransformation>,
+    pub dx: f32,
+    pub dy: f32,
+    pub shear: f32,
+    /// Area of the items divided by the area of a lattice cell
+    pub density: f32,
+}
+
+impl Lattice {
+    /// Finds the densest lattice packing of an item under its allowed rotations
+    pub fn best(item: &Item, config: &LatticeConfig) -> Option<Lattice> {
+        let rotations = match &item.allowed_rotation {
+            RotationRange::None => vec![0.0],
+            RotationRange::Discrete(rs) => rs.clone(),
+            RotationRange::Continuous => (0..config.n_continuous_rotations)
+                .map(|i| 2.0 * PI * i as f32 / config.n_continuous_rotations as f32)
+                .collect(),
+        };
+        let rotation_allowed = |r: f32| match &item.allowed_rotation {
+            RotationRange::Continuous => true,
+            _ => rotations.iter().any(|ar| same_rotation(*ar, r)),
+        };
+
+        let mut motifs = vec![];
+        for &r in rotations.iter() {
+            let single = vec![DTransformation::new(r, (0.0, 0.0))];
+            if rotation_allowed(r + PI) {
+                //double lattice: a copy rotated by 180°, placed as densely as possible against the first
+                let mut base = item.shape_cd.as_ref().clone();
+                base.transform_from(&item.shape_cd, &single[0].compose());
+                if let Some((rel, _, _)) = densest_contact(&base, &item.shape_cd, (r + PI).rem_euclid(2.0 * PI), config.n_contact_samples, config.n_bisections) {
+                    motifs.push(vec![single[0], rel]);
+                }
+            }
+            motifs.push(single);
+        }
+
+        motifs.into_iter()
+            .filter_map(|motif| Self::fit(item, motif, config))
+            .max_by_key(|l| OrderedFloat(l.density))
+    }
+
+    /// Determines the smallest lattice vectors for a motif
+    fn fit(item: &Item, motif: Vec<DTransformation>, config: &LatticeConfig) -> Option<Lattice> {
+        let shapes = motif.iter()
+            .map(|dt| {
+                let mut s = item.shape_cd.as_ref().clone();
+                s.transform_from(&item.shape_cd, &dt.compose());
+                s
+            })
+            .collect_vec();
+        let (x_min, x_max) = shapes.iter().map(|s| (s.bbox.x_min, s.bbox.x_max))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(a, b), (c, d)| (a.min(c), b.max(d)));
+        let (y_min, y_max) = shapes.iter().map(|s| (s.bbox.y_min, s.bbox.y_max))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(a, b), (c, d)| (a.min(c), b.max(d)));
+        let mut buff = item.shape_cd.as_ref().clone();
+
+        //whether the motif collides with copies of itself at the given offsets
+        let mut collides = |offsets: &[(f32, f32)]| {
+            offsets.iter().any(|&(ox, oy)| {
+                motif.iter().any(|dt| {
+                    let (tx, ty) = dt.translation();
+                    let moved = DTransformation::new(dt.rotation(), (tx + ox, ty + oy));
+                    buff.transform_from(&item.shape_cd, &moved.compose());
+                    shapes.iter().any(|s| overlap_area(s, &buff) > s.area * LATTICE_OVERLAP_TOLERANCE)
+                })
+            })
+        };
+
+        //smallest horizontal period
+        let dx = min_clear_offset(x_max - x_min, config.n_bisections, |d| collides(&[(d, 0.0)]));
+
+        //smallest vertical period for every sampled shear, no copy in the rows above may collide with the motif.
+        //Only the copies that are horizontally within reach are checked, the rows below follow by symmetry.
+        let (width, height) = (x_max - x_min, y_max - y_min);
+        let (shear, dy) = (0..config.n_shear_samples)
+            .map(|k| {
+                let shear = dx * k as f32 / config.n_shear_samples as f32;
+                let dy = min_clear_offset(height, config.n_bisections, |d| {
+                    let offsets = (1..)
+                        .take_while(|j| *j as f32 * d < height)
+                        .flat_map(|j| {
+                            let (row_x, row_y) = (j as f32 * shear, j as f32 * d);
+                            let i_min = ((-width - row_x) / dx).floor() as i32;
+                            let i_max = ((width - row_x) / dx).ceil() as i32;
+                            (i_min..=i_max).map(move |i| (row_x + i as f32 * dx, row_y))
+                        })
+                        .collect_vec();
+                    collides(&offsets)
+                });
+                (shear, dy)
+            })
+            .min_by_key(|(_, dy)| OrderedFloat(*dy))?;
+
+        let density = motif.len() as f32 * item.shape_cd.area / (dx * dy);
+        debug!("[LATT] item {}: motif of {} with dx: {:.3}, dy: {:.3}, shear: {:.3} (density: {:.3}%)", item.id, motif.len(), dx, dy, shear, density * 100.0);
+
+        Some(Lattice { item_id: item.id, motif, dx, dy, shear, density })
+    }
+
+    /// Places copies of the item on the lattice, column by column starting at `x_start`, until `n_copies` are placed
+    /// or a column could not hold any copy. Only positions without any collision are used.
+    /// Returns the number of placed copies.
+    pub fn seed(&self, prob: &mut SPProblem, item: &Item, x_start: f32, n_copies: usize) -> usize {
+        let container_bbox = prob.layout.container.outer_cd.bbox;
+        let mut buff = item.shape_cd.as_ref().clone();
+
+        //offset to align the bottom-left of the motif with the start of the region
+        let motif_bboxes = self.motif.iter()
+            .map(|dt| {
+                buff.transform_from(&item.shape_cd, &dt.compose());
+                buff.bbox
+            })
+            .collect_vec();
+        let x_offset = x_start - motif_bboxes.iter().map(|b| b.x_min).fold(f32::INFINITY, f32::min);
+        let y_offset = container_bbox.y_min - motif_bboxes.iter().map(|b| b.y_min).fold(f32::INFINITY, f32::min);
+        let n_rows = (container_bbox.height() / self.dy).ceil() as usize;
+
+        let mut n_placed = 0;
+        for i in 0.. {
+            let mut n_placed_in_column = 0;
+            for j in 0..n_rows {
+                let row_shift = (j as f32 * self.shear).rem_euclid(self.dx);
+                for dt in self.motif.iter() {
+                    if n_placed == n_copies {
+                        return n_placed;
+                    }
+                    let (tx, ty) = dt.translation();
+                    let d_transf = DTransformation::new(dt.rotation(), (
+                        tx + x_offset + i as f32 * self.dx + row_shift,
+                        ty + y_offset + j as f32 * self.dy,
+                    ));
+                    buff.transform_from(&item.shape_cd, &d_transf.compose());
+                    if !prob.layout.cde().detect_poly_collision(&buff, &NoFilter) {
+                        prob.place_item(SPPlacement { item_id: item.id, d_transf });
+                        n_placed += 1;
+                        n_placed_in_column += 1;
+                    }
+                }
+            }
+            if n_placed_in_column == 0 {
+                break;
+            }
+        }
+        n_placed
+    }
+}
+
+/// Smallest offset in `[0, max]` for which `collides` is false, found by bisection (assuming monotonicity)
+fn min_clear_offset(max: f32, n_bisections: usize, mut collides: impl FnMut(f32) -> bool) -> f32 {
+    let (mut blocked, mut clear) = (0.0, max);
+    for _ in 0..n_bisections {
+        let mid = (blocked + clear) / 2.0;
+        match collides(mid) {
+            true => blocked = mid,
+            false => clear = mid,
+        }
+    }
+    clear
+}
+
+fn same_rotation(r1: f32, r2: f32) -> bool {
+    let diff = (r1 - r2).rem_euclid(2.0 * PI);
+    approx_eq!(f32, diff, 0.0, epsilon = 1e-3) || approx_eq!(f32, diff, 2.0 * PI, epsilon = 1e-3)
+}
@@ -1,22 +1,86 @@
+use crate::config::{ConstructionConfig, LatticeConfig};
+use crate::consts::LBF_DEFAULT_STRATEGY;
 use crate::eval::lbf_evaluator::LBFEvaluator;
 use crate::eval::sample_eval::SampleEval;
-use crate::sample::search::{search_placement, SampleConfig};
+use crate::eval::shape_cache::ShapeCache;
+use crate::optimizer::lattice::Lattice;
+use crate::sample::nfp_sampler::NFPCache;
+use crate::sample::free_space_sampler::FreeSpaceRaster;
+use crate::sample::search::{refine_samples, sample_placements, search_placement, SampleConfig};
 use itertools::Itertools;
-use log::debug;
+use log::{debug, info};
 use ordered_float::OrderedFloat;
 use rand::prelude::SmallRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 use std::cmp::Reverse;
 use std::iter;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use jagua_rs::entities::Instance;
 use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem};
 use crate::util::assertions;
 
+/// Order in which items are fed to the constructor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemOrdering {
+    /// Descending convex hull area multiplied by diameter
+    ConvexHullAreaDiameter,
+    /// Descending area
+    Area,
+    /// Descending length of the longest side of the bounding box
+    BBoxLength,
+    /// Uniformly shuffled
+    Random,
+}
+
+/// How the constructor scores collision-free placements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlacementScoring {
+    /// Prefer placements close to the left-bottom corner of the container
+    LeftBottom,
+    /// Prefer left-bottom placements that touch other items or the container on as many sides as possible
+    MaxContact,
+}
+
+/// How the constructor determines the width of the strip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InitialWidthStrategy {
+    /// Grow the strip by the given ratio whenever an item does not fit
+    Grow(f32),
+    /// Construct by growing, then binary search between the area lower bound and the constructed width
+    /// for a narrower width at which all items can still be placed
+    BinarySearch {
+        grow_ratio: f32,
+        n_iterations: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstructionStrategy {
+    pub ordering: ItemOrdering,
+    pub scoring: PlacementScoring,
+    pub width: InitialWidthStrategy,
+}
+
 pub struct LBFBuilder {
     pub instance: SPInstance,
     pub prob: SPProblem,
     pub rng: SmallRng,
     pub sample_config: SampleConfig,
+    pub nfp_cache: NFPCache,
+    /// Rasterization of the layout for free-space sampling, updated incrementally
+    pub fs_raster: FreeSpaceRaster,
+    pub shape_cache: ShapeCache,
+    pub strategy: ConstructionStrategy,
+    /// Seeds high-demand items in lattice packings before placing the other items, disabled if `None`
+    pub lattice: Option<LatticeConfig>,
+    /// Splits the sampling of every placement over this many partitions evaluated in parallel, sequential if `None`
+    pub search_partitions: Option<usize>,
+    /// NFP caches and rasters of the parallel search partitions
+    partition_nfp_caches: Vec<(NFPCache, FreeSpaceRaster)>,
+    /// Time spent in [`LBFBuilder::construct`]
+    pub construction_time: Duration,
 }
 
 impl LBFBuilder {
@@ -26,24 +90,99 @@ impl LBFBuilder {
         sample_config: SampleConfig,
     ) -> Self {
         let prob = SPProblem::new(instance.clone());
+        let shape_cache = ShapeCache::new(&instance);
 
         Self {
             instance,
             prob,
             rng,
             sample_config,
+            nfp_cache: NFPCache::default(),
+            fs_raster: FreeSpaceRaster::default(),
+            shape_cache,
+            strategy: LBF_DEFAULT_STRATEGY,
+            lattice: None,
+            search_partitions: None,
+            partition_nfp_caches: vec![],
+            construction_time: Duration::ZERO,
         }
     }
 
+    pub fn with_strategy(mut self, strategy: ConstructionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_lattice(mut self, lattice: Option<LatticeConfig>) -> Self {
+        self.lattice = lattice;
+        self
+    }
+
+    pub fn with_search_partitions(mut self, search_partitions: Option<usize>) -> Self {
+        assert!(search_partitions.is_none_or(|n| n > 0));
+        self.search_partitions = search_partitions;
+        self
+    }
+
     pub fn construct(mut self) -> Self {
         let start = Instant::now();
+        let lattices = self.find_lattices();
+        self.seed_lattices(&lattices);
+        let sorted_item_indices = self.item_order();
+
+        debug!("[CONSTR] placing items in order: {:?}",sorted_item_indices);
+
+        let grow_ratio = match self.strategy.width {
+            InitialWidthStrategy::Grow(ratio) => ratio,
+            InitialWidthStrategy::BinarySearch { grow_ratio, .. } => grow_ratio,
+        };
+
+        for &item_id in &sorted_item_indices {
+            self.place_item(item_id, grow_ratio);
+        }
+
+        self.prob.fit_strip();
+        debug!("[CONSTR] placed all items in width: {:.3} (in {:?})",self.prob.strip_width(), start.elapsed());
+
+        if let InitialWidthStrategy::BinarySearch { n_iterations, .. } = self.strategy.width {
+            let strip_height = self.prob.layout.container.outer_cd.bbox.height();
+            let total_item_area = self.instance.items.iter()
+                .map(|(item, qty)| item.shape_cd.area * *qty as f32)
+                .sum::<f32>();
+
+            let mut lower = total_item_area / strip_height;
+            let mut upper = self.prob.strip_width();
+            for _ in 0..n_iterations {
+                let width = (lower + upper) / 2.0;
+                match self.construct_at_width(&lattices, width) {
+                    true => {
+                        upper = self.prob.strip_width();
+                        debug!("[CONSTR] bisection: placed all items in width: {:.3}", upper);
+                    }
+                    false => {
+                        lower = width;
+                        debug!("[CONSTR] bisection: failed to place all items in width: {:.3}", width);
+                    }
+                }
+            }
+            debug!("[CONSTR] bisection finished at width: {:.3} (in {:?})",self.prob.strip_width(), start.elapsed());
+        }
+        self.construction_time = start.elapsed();
+        self
+    }
+
+    fn item_order(&mut self) -> Vec<usize> {
         let n_items = self.instance.items.len();
-        let sorted_item_indices = (0..n_items)
+        let mut item_indices = (0..n_items)
             .sorted_by_cached_key(|id| {
                 let item_shape = self.instance.item(*id).shape_cd.as_ref();
-                let convex_hull_area = item_shape.surrogate().convex_hull_area;
-                let diameter = item_shape.diameter;
-                Reverse(OrderedFloat(convex_hull_area * diameter))
+                let key = match self.strategy.ordering {
+                    ItemOrdering::ConvexHullAreaDiameter => item_shape.surrogate().convex_hull_area * item_shape.diameter,
+                    ItemOrdering::Area => item_shape.area,
+                    ItemOrdering::BBoxLength => item_shape.bbox.width().max(item_shape.bbox.height()),
+                    ItemOrdering::Random => 0.0,
+                };
+                Reverse(OrderedFloat(key))
             })
             .map(|id| {
                 let missing_qty = self.prob.item_demand_qtys[id];
@@ -52,18 +191,62 @@ impl LBFBuilder {
             .flatten()
             .collect_vec();
 
-        debug!("[CONSTR] placing items in order: {:?}",sorted_item_indices);
-
-        for item_id in sorted_item_indices {
-            self.place_item(item_id);
+        if self.strategy.ordering == ItemOrdering::Random {
+            item_indices.shuffle(&mut self.rng);
         }
+        item_indices
+    }
+
+    /// Attempts to place all items in a strip of fixed width.
+    /// Only replaces the current problem if all items could be placed.
+    fn construct_at_width(&mut self, lattices: &[Lattice], width: f32) -> bool {
+        let mut prob = SPProblem::new(self.instance.clone());
+        prob.change_strip_width(width);
+        let prev_prob = std::mem::replace(&mut self.prob, prob);
 
+        self.seed_lattices(lattices);
+        for item_id in self.item_order() {
+            match self.find_placement(item_id) {
+                Some(p_opt) => {
+                    self.prob.place_item(p_opt);
+                }
+                None => {
+                    self.prob = prev_prob;
+                    return false;
+                }
+            }
+        }
         self.prob.fit_strip();
-        debug!("[CONSTR] placed all items in width: {:.3} (in {:?})",self.prob.strip_width(), start.elapsed());
-        self
+        true
+    }
+
+    /// Finds lattice packings for all items with sufficient demand, most demanded first
+    fn find_lattices(&self) -> Vec<Lattice> {
+        let Some(config) = self.lattice else {
+            return vec![];
+        };
+        self.instance.items.iter()
+            .filter(|(_, qty)| *qty >= config.min_demand)
+            .sorted_by_key(|(_, qty)| Reverse(*qty))
+            .filter_map(|(item, _)| Lattice::best(item, &config))
+            .collect()
+    }
+
+    /// Tiles the missing copies of every lattice item into consecutive regions, starting at the left of the strip
+    fn seed_lattices(&mut self, lattices: &[Lattice]) {
+        let mut x_start = self.prob.layout.container.outer_cd.bbox.x_min;
+        for lattice in lattices {
+            let item = self.instance.item(lattice.item_id);
+            let n_missing = self.prob.item_demand_qtys[lattice.item_id];
+            let n_placed = lattice.seed(&mut self.prob, item, x_start, n_missing);
+            x_start = self.prob.layout.placed_items.values()
+                .map(|pi| pi.shape.bbox.x_max)
+                .fold(x_start, f32::max);
+            debug!("[CONSTR] seeded {}/{} copies of item {} in a lattice (density: {:.3}%)", n_placed, n_missing, lattice.item_id, lattice.density * 100.0);
+        }
     }
 
-    fn place_item(&mut self, item_id: usize) {
+    fn place_item(&mut self, item_id: usize, grow_ratio: f32) {
         match self.find_placement(item_id) {
             Some(p_opt) => {
                 self.prob.place_item(p_opt);
@@ -71,9 +254,9 @@ impl LBFBuilder {
             }
             None => {
                 debug!("[CONSTR] failed to place item with id {}, expanding strip width",item_id);
-                self.prob.change_strip_width(self.prob.strip_width() * 1.2);
+                self.prob.change_strip_width(self.prob.strip_width() * grow_ratio);
                 assert!(assertions::strip_width_is_in_check(&self.prob), "strip-width is running away (>{:.3}), item {item_id} does not seem to fit into the strip", self.prob.strip_width());          
-                self.place_item(item_id);
+                self.place_item(item_id, grow_ratio);
             }
         }
     }
@@ -81,9 +264,39 @@ impl LBFBuilder {
     fn find_placement(&mut self, item_id: usize) -> Option<SPPlacement> {
         let layout = &self.prob.layout;
         let item = self.instance.item(item_id);
-        let evaluator = LBFEvaluator::new(layout, item);
+        let (scoring, rotated_shapes) = (self.strategy.scoring, self.shape_cache.get(item.id));
+        let new_evaluator = || LBFEvaluator::new(layout, item, scoring).with_rotated_shapes(rotated_shapes);
 
-        let (best_sample, _) = search_placement(layout, item, None, evaluator, self.sample_config, &mut self.rng);
+        let best_sample = match self.search_partitions {
+            None => search_placement(layout, item, None, new_evaluator(), self.sample_config, &mut self.rng, &mut self.nfp_cache, &mut self.fs_raster).0,
+            Some(n_partitions) => {
+                //seeds are drawn up front, so the outcome does not depend on the scheduling of the partitions
+                let seeds = (0..n_partitions).map(|_| self.rng.random::<u64>()).collect_vec();
+                let configs = partition_sample_config(self.sample_config, n_partitions);
+                self.partition_nfp_caches.resize_with(n_partitions, Default::default);
+
+                //only the sampling phase is partitioned, every partition keeps its own best samples
+                let bbox = layout.container.outer_cd.bbox;
+                let partition_samples: Vec<_> = self.partition_nfp_caches.par_iter_mut()
+                    .zip(configs.into_par_iter())
+                    .zip(seeds.into_par_iter())
+                    .map(|(((nfp_cache, fs_raster), config), seed)| {
+                        let mut rng = SmallRng::seed_from_u64(seed);
+                        sample_placements(layout, bbox, item, None, &mut new_evaluator(), config, &mut rng, nfp_cache, fs_raster)
+                    })
+                    .collect();
+
+                //deterministic reduction: the best samples of all partitions are merged in partition order,
+                //after which the global best ones are refined sequentially, as in a single search
+                let best_samples = partition_samples.into_iter()
+                    .reduce(|mut acc, bs| {
+                        acc.merge(&bs);
+                        acc
+                    })
+                    .expect("at least one partition");
+                refine_samples(best_samples, item, &mut new_evaluator(), self.sample_config, &mut self.rng)
+            }
+        };
 
         match best_sample {
             Some((d_transf, SampleEval::Clear { .. })) => {
@@ -93,3 +306,47 @@ impl LBFBuilder {
         }
     }
 }
+
+/// Splits the samples of a config as evenly as possible over `n` partitions.
+/// Every partition keeps as many best samples as there are coordinate descents, so the merged best samples match a single search.
+fn partition_sample_config(config: SampleConfig, n: usize) -> Vec<SampleConfig> {
+    let share = |total: usize, i: usize| total / n + usize::from(i < total % n);
+    (0..n)
+        .map(|i| SampleConfig {
+            n_container_samples: share(config.n_container_samples, i),
+            n_focussed_samples: share(config.n_focussed_samples, i),
+            n_nfp_samples: share(config.n_nfp_samples, i),
+            ..config
+        })
+        .collect()
+}
+
+/// Constructs an initial solution with every configured strategy in parallel and keeps the narrowest one.
+/// Ties are broken in favor of the strategy listed first, so the result is deterministic for a given seed.
+pub fn construct_best(instance: &SPInstance, mut rng: SmallRng, config: &ConstructionConfig) -> LBFBuilder {
+    let start = Instant::now();
+    let jobs = config.strategies.iter()
+        .map(|s| (*s, rng.random::<u64>()))
+        .collect_vec();
+
+    let builders: Vec<LBFBuilder> = jobs.into_par_iter()
+        .map(|(strategy, seed)| {
+            LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(seed), config.sample_config)
+                .with_strategy(strategy)
+                .with_lattice(config.lattice)
+                .with_search_partitions(config.search_partitions)
+                .construct()
+        })
+        .collect();
+
+    for b in builders.iter() {
+        debug!("[CONSTR] strategy {:?} reached width: {:.3} (in {:?})", b.strategy, b.prob.strip_width(), b.construction_time);
+    }
+
+    let best = builders.into_iter()
+        .min_by_key(|b| OrderedFloat(b.prob.strip_width()))
+        .expect("at least one construction strategy should be configured");
+
+    info!("[CONSTR] best initial solution by {:?}, width: {:.3} (in {:?})", best.strategy, best.prob.strip_width(), start.elapsed());
+    best
+}
@@ -1,30 +1,49 @@
 use crate::config::*;
-use crate::optimizer::lbf::LBFBuilder;
+use crate::optimizer::lbf::construct_best;
 use crate::optimizer::separator::Separator;
 use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
 use rand::prelude::SmallRng;
 use rand::{RngCore, SeedableRng};
 use std::time::{Duration};
-use crate::consts::LBF_SAMPLE_CONFIG;
+use crate::optimizer::cluster::cluster_complementary_items;
+use crate::optimizer::compact::compaction_phase;
 use crate::optimizer::compress::compression_phase;
 use crate::optimizer::explore::exploration_phase;
 use crate::util::listener::{ReportType, SolutionListener};
 use crate::util::terminator::Terminator;
 
+pub mod lattice;
 pub mod lbf;
 pub mod separator;
 mod worker;
 pub mod explore;
+pub mod cluster;
 pub mod compress;
+pub mod compact;
+pub mod disruption;
+pub mod elite;
+pub mod ruin_recreate;
+pub mod shrink_schedule;
+pub mod split;
+pub mod window;
 
-pub fn optimize(instance: SPInstance, mut rng: SmallRng, sol_listener: &mut impl SolutionListener, terminator: &mut impl Terminator, expl_config: &ExplorationConfig, cmpr_config: &CompressionConfig) -> SPSolution {
+pub fn optimize(instance: SPInstance, mut rng: SmallRng, sol_listener: &mut impl SolutionListener, terminator: &mut impl Terminator, constr_config: &ConstructionConfig, expl_config: &ExplorationConfig, cmpr_config: &CompressionConfig) -> SPSolution {
     let mut next_rng = || SmallRng::seed_from_u64(rng.next_u64());
-    let builder = LBFBuilder::new(instance.clone(), next_rng(), LBF_SAMPLE_CONFIG).construct();
+
+    //exploration optionally works on an instance in which complementary items are merged
+    let clustered = expl_config.clustering.as_ref()
+        .and_then(|c| cluster_complementary_items(&instance, c));
+    let expl_instance = match &clustered {
+        Some(c) => c.instance.clone(),
+        None => instance.clone(),
+    };
+
+    let builder = construct_best(&expl_instance, next_rng(), constr_config);
 
     terminator.new_timeout(expl_config.time_limit);
     let mut expl_separator = Separator::new(builder.instance, builder.prob, next_rng(), expl_config.separator_config);
     let solutions = exploration_phase(
-        &instance,
+        &expl_instance,
         &mut expl_separator,
         sol_listener,
         terminator,
@@ -32,8 +51,18 @@ pub fn optimize(instance: SPInstance, mut rng: SmallRng, sol_listener: &mut impl
     );
     let final_explore_sol = solutions.last().unwrap().clone();
 
+    //split the composite items back into their parts for compression, repairing the split layout if needed
     terminator.new_timeout(cmpr_config.time_limit);
-    let mut cmpr_separator = Separator::new(expl_separator.instance, expl_separator.prob, next_rng(), cmpr_config.separator_config);
+    let (cmpr_prob, final_explore_sol) = match &clustered {
+        Some(c) => {
+            let prob = c.split_feasible(&final_explore_sol, &instance, next_rng(), cmpr_config.separator_config, constr_config, terminator, sol_listener);
+            let sol = prob.save();
+            (prob, sol)
+        }
+        None => (expl_separator.prob, final_explore_sol),
+    };
+
+    let mut cmpr_separator = Separator::new(instance.clone(), cmpr_prob, next_rng(), cmpr_config.separator_config);
     let cmpr_sol = compression_phase(
         &instance,
         &mut cmpr_separator,
@@ -43,7 +72,12 @@ pub fn optimize(instance: SPInstance, mut rng: SmallRng, sol_listener: &mut impl
         cmpr_config,
     );
 
-    sol_listener.report(ReportType::Final, &cmpr_sol, &instance);
+    let final_sol = match &cmpr_config.compaction {
+        Some(compaction_config) => compaction_phase(&instance, &cmpr_sol, sol_listener, compaction_config),
+        None => cmpr_sol,
+    };
+
+    sol_listener.report(ReportType::Final, &final_sol, &instance);
 
-    cmpr_sol
+    final_sol
 }
\ No newline at end of file
@@ -0,0 +1,51 @@
+use std::cmp::Reverse;
+use itertools::Itertools;
+use jagua_rs::entities::Instance;
+use jagua_rs::probs::spp::entities::SPPlacement;
+use log::{debug, info};
+use ordered_float::OrderedFloat;
+use crate::config::RuinRecreateConfig;
+use crate::eval::penalty_evaluator::PenaltyEvaluator;
+use crate::optimizer::disruption::items_in_random_slab;
+use crate::optimizer::separator::Separator;
+use crate::quantify::tracker::CollisionTracker;
+use crate::sample::free_space_sampler::FreeSpaceRaster;
+use crate::sample::nfp_sampler::NFPCache;
+use crate::sample::search::search_placement;
+
+/// Removes all items in a random vertical window of the strip and reinserts them one by one,
+/// largest first, at the position with the least overlap found by [`search_placement`].
+/// The GLS weights of the items outside the window are preserved.
+pub fn ruin_and_recreate(sep: &mut Separator, config: &RuinRecreateConfig) {
+    let (x_min, x_max, pks) = items_in_random_slab(sep, config.window_width_ratio);
+    info!("[EXPL] ruin and recreate of window [{:.3}, {:.3}] ({} items)", x_min, x_max, pks.len());
+
+    //ruin: the tracker is out of sync with the layout until it is rebuilt below
+    let item_ids = pks.into_iter()
+        .map(|pk| {
+            let item_id = sep.prob.layout.placed_items[pk].item_id;
+            sep.prob.remove_item(pk);
+            item_id
+        })
+        .sorted_by_cached_key(|id| {
+            let shape = sep.instance.item(*id).shape_cd.as_ref();
+            Reverse(OrderedFloat(shape.surrogate().convex_hull_area))
+        })
+        .collect_vec();
+
+    //recreate
+    let mut nfp_cache = NFPCache::default();
+    let mut fs_raster = FreeSpaceRaster::default();
+    for item_id in item_ids {
+        let item = sep.instance.item(item_id);
+        let evaluator = PenaltyEvaluator::new(&sep.prob.layout, item, sep.config.overlap_quantifier);
+        let (best_sample, _) = search_placement(&sep.prob.layout, item, None, evaluator, config.sample_config, &mut sep.rng, &mut nfp_cache, &mut fs_raster);
+        let (d_transf, eval) = best_sample.expect("at least one sample should be evaluated");
+        debug!("[EXPL] reinserted item {} at [{}] ({:?})", item_id, d_transf, eval);
+        sep.prob.place_item(SPPlacement { item_id, d_transf });
+    }
+
+    //rebuild the tracker, the items which were not ruined keep their key and their GLS weights
+    let prev_ct = std::mem::replace(&mut sep.ct, CollisionTracker::new(&sep.prob.layout, sep.config.overlap_quantifier));
+    sep.ct.inherit_weights(&prev_ct, 1.0);
+}
@@ -1,8 +1,13 @@
-use crate::optimizer::worker::{SepStats, SeparatorWorker};
+use crate::optimizer::worker::{sync_items, SepStats, SeparatorWorker};
 use crate::optimizer::Terminator;
 use crate::quantify::tracker::{CTSnapshot, CollisionTracker};
+use crate::quantify::weights::GLSWeightConfig;
+use crate::quantify::OverlapQuantifier;
+use crate::eval::shape_cache::ShapeCache;
+use crate::sample::nfp_sampler::NFPCache;
+use crate::sample::free_space_sampler::FreeSpaceRaster;
 use crate::sample::search::SampleConfig;
-use crate::util::assertions::tracker_matches_layout;
+use crate::util::assertions::{tracker_matches_layout, worker_matches_master};
 use crate::{FMT};
 use itertools::Itertools;
 use jagua_rs::entities::PItemKey;
@@ -15,6 +20,7 @@ use rand::{Rng, SeedableRng};
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use rayon::ThreadPool;
+use std::sync::Arc;
 use std::time::Instant;
 use crate::util::listener::{ReportType, SolutionListener};
 
@@ -25,6 +31,8 @@ pub struct SeparatorConfig {
     pub n_workers: usize,
     pub log_level: Level,
     pub sample_config: SampleConfig,
+    pub weight_config: GLSWeightConfig,
+    pub overlap_quantifier: OverlapQuantifier,
 }
 
 pub struct Separator {
@@ -35,18 +43,32 @@ pub struct Separator {
     pub workers: Vec<SeparatorWorker>,
     pub config: SeparatorConfig,
     pub pool: ThreadPool,
+    /// Whether all workers are identical to the master, allowing them to be synced incrementally
+    workers_synced: bool,
+    /// Horizontal range of the strip to which separation is restricted, see [`Separator::set_window`]
+    window: Option<(f32, f32)>,
+    /// Window to restore after the next separation, see [`Separator::lift_window`]
+    lifted_window: Option<(f32, f32)>,
 }
 
 impl Separator {
     pub fn new(instance: SPInstance, prob: SPProblem, mut rng: SmallRng, config: SeparatorConfig) -> Self {
-        let ct = CollisionTracker::new(&prob.layout);
+        let ct = CollisionTracker::new(&prob.layout, config.overlap_quantifier);
+        let shape_cache = Arc::new(ShapeCache::new(&instance));
+        //a single copy of the instance is shared by all workers, each of them only holds its own layout
+        let shared_instance = Arc::new(instance.clone());
         let workers = (0..config.n_workers).map(|_|
             SeparatorWorker {
-                instance: instance.clone(),
-                prob: prob.clone(),
+                instance: shared_instance.clone(),
+                layout: prob.layout.clone(),
                 ct: ct.clone(),
                 rng: SmallRng::seed_from_u64(rng.random()),
                 sample_config: config.sample_config.clone(),
+                nfp_cache: NFPCache::default(),
+                fs_raster: FreeSpaceRaster::default(),
+                shape_cache: shape_cache.clone(),
+                window: None,
+                moved: vec![],
             }).collect();
         let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(config.n_workers).build().unwrap();
@@ -59,10 +81,15 @@ impl Separator {
             workers,
             config,
             pool,
+            workers_synced: true,
+            window: None,
+            lifted_window: None,
         }
     }
 
     pub fn separate(&mut self, term: &impl Terminator, sol_listener: &mut impl SolutionListener) -> (SPSolution, CTSnapshot) {
+        //the master might have been modified directly since the last separation
+        self.workers_synced = false;
         let mut min_loss_sol = (self.prob.save(), self.ct.save());
         let mut min_loss = self.ct.get_total_loss();
         log!(self.config.log_level,"[SEP] separating at width: {:.3} and loss: {} ", self.prob.strip_width(), FMT().fmt2(min_loss));
@@ -122,7 +149,7 @@ impl Separator {
                     n_iter_no_improvement += 1;
                 }
 
-                self.ct.increment_weights();
+                self.increment_weights();
                 n_iter += 1;
             }
 
@@ -132,6 +159,10 @@ impl Separator {
                 n_strikes = 0;
             }
             self.rollback(&min_loss_sol.0, Some(&min_loss_sol.1));
+            if let Some(n) = self.config.weight_config.reset_after_strikes && n_strikes > 0 && n_strikes % n == 0 {
+                debug!("[SEP] [s:{n_strikes},i:{n_iter}] resetting GLS weights");
+                self.ct.reset_weights();
+            }
         }
         let secs = start.elapsed().as_secs_f32();
         log!(self.config.log_level, "[SEP] finished, evals/s: {} K, evals/move: {}, moves/s: {}, iter/s: {}, #workers: {}, total {:.3}s",
@@ -143,40 +174,68 @@ impl Separator {
             FMT().fmt2(secs),
         );
 
+        if let Some(window) = self.lifted_window.take() {
+            self.set_window(Some(window));
+        }
+
         (min_loss_sol.0, min_loss_sol.1)
     }
 
     fn move_colliding_items(&mut self) -> SepStats {
-        let master_sol = self.prob.save();
+        if !self.workers_synced {
+            // Fully restore the workers from the master
+            let master_sol = self.prob.save();
+            self.pool.install(|| {
+                self.workers.par_iter_mut().for_each(|worker| worker.load(&master_sol, &self.ct));
+            });
+            self.workers_synced = true;
+        }
 
         // Use the local thread pool (instead of global one) to maximize cache locality
         let sep_report = self.pool.install(|| {
-            self.workers.par_iter_mut().map(|worker| {
-                // Sync the workers with the master
-                worker.load(&master_sol, &self.ct);
-                // Let them modify
-                worker.move_colliding_items()
-            }).sum()
+            self.workers.par_iter_mut()
+                .map(|worker| worker.move_colliding_items())
+                .sum()
         });
 
         debug!("[MOD] optimizers w_o's: {:?}",self.workers.iter().map(|opt| opt.ct.get_total_weighted_loss()).collect_vec());
 
         // Check which worker has the lowest total weighted loss
-        let best_opt = self.workers.iter_mut()
-            .min_by_key(|opt| OrderedFloat(opt.ct.get_total_weighted_loss()))
-            .map(|opt| (opt.prob.save(), &opt.ct))
+        let best_idx = self.workers.iter()
+            .position_min_by_key(|opt| OrderedFloat(opt.ct.get_total_weighted_loss()))
             .unwrap();
 
-        // Sync the master with the best optimizer
-        self.prob.restore(&best_opt.0);
-        self.ct = best_opt.1.clone();
+        // Sync the master with the items moved by the best worker
+        let best = &self.workers[best_idx];
+        let master_changes = best.moved.iter().copied().unique().collect_vec();
+        sync_items(&self.instance, &mut self.prob.layout, &mut self.ct, &best.layout, &best.ct, &master_changes);
+
+        // Sync the workers with the master, only the items moved by either of them need to be copied
+        let (layout, ct) = (&self.prob.layout, &self.ct);
+        self.pool.install(|| {
+            self.workers.par_iter_mut().for_each(|worker| worker.sync(layout, ct, &master_changes));
+        });
+        debug_assert!(self.workers.iter().all(|w| worker_matches_master(&w.layout, &w.ct, &self.prob.layout, &self.ct)));
 
         sep_report
     }
 
+    /// Increments the GLS weights of the master and (if in sync) of all workers
+    fn increment_weights(&mut self) {
+        self.ct.increment_weights(&self.config.weight_config);
+        if self.workers_synced {
+            //the workers hold the same losses as the master, so the same update keeps them in sync
+            let weight_config = &self.config.weight_config;
+            self.pool.install(|| {
+                self.workers.par_iter_mut().for_each(|worker| worker.ct.increment_weights(weight_config));
+            });
+        }
+    }
+
     pub fn rollback(&mut self, sol: &SPSolution, ots: Option<&CTSnapshot>) {
         debug_assert!(sol.strip_width() == self.prob.strip_width());
         self.prob.restore(sol);
+        self.workers_synced = false;
 
         match ots {
             Some(ots) => {
@@ -185,7 +244,7 @@ impl Separator {
             }
             None => {
                 //otherwise, rebuild it
-                self.ct = CollisionTracker::new(&self.prob.layout);
+                self.ct = CollisionTracker::new(&self.prob.layout, self.config.overlap_quantifier);
             }
         }
     }
@@ -217,38 +276,86 @@ impl Separator {
         new_pk
     }
 
+    /// Restricts separation to the items whose centroid lies in the horizontal range `window` of the strip.
+    /// These items are kept inside the window (see [`WindowEvaluator`](crate::eval::window_evaluator::WindowEvaluator)),
+    /// all other items are frozen and only act as hazards. Separation is global again if `None`.
+    pub fn set_window(&mut self, window: Option<(f32, f32)>) {
+        self.window = window;
+        self.lifted_window = None;
+        self.workers.iter_mut().for_each(|w| w.window = window);
+        match window {
+            Some((x_min, x_max)) => debug!("[SEP] restricted to window [{:.3}, {:.3}]", x_min, x_max),
+            None => debug!("[SEP] window cleared"),
+        }
+    }
+
+    /// Lifts the window for the next separation only, after which it is restored.
+    /// Needed after modifications over the whole strip, which can leave collisions outside the window.
+    pub fn lift_window(&mut self) {
+        if let Some(window) = self.window {
+            self.set_window(None);
+            self.lifted_window = Some(window);
+        }
+    }
+
+    pub fn window(&self) -> Option<(f32, f32)> {
+        self.window
+    }
+
     pub fn change_strip_width(&mut self, new_width: f32, split_position: Option<f32>) {
         //if no split position is provided, use the center of the strip
         let split_position = split_position.unwrap_or(self.prob.strip_width() / 2.0);
-        let delta = new_width - self.prob.strip_width();
+        self.change_strip_width_at(new_width, &[split_position]);
+    }
 
-        //shift all items right of the split position
+    /// Changes the width of the strip, distributing the change evenly over all split positions
+    pub fn change_strip_width_at(&mut self, new_width: f32, split_positions: &[f32]) {
+        assert!(!split_positions.is_empty(), "at least one split position is required");
+        let delta_per_split = (new_width - self.prob.strip_width()) / split_positions.len() as f32;
+
+        //shift all items by the change in width of every split position left of them
         let items_to_shift = self.prob.layout.placed_items.iter()
-            .filter(|(_, pi)| pi.shape.centroid().0 > split_position)
-            .map(|(k, pi)| (k, pi.d_transf))
+            .map(|(k, pi)| {
+                let centroid_x = pi.shape.centroid().0;
+                let n_splits_left = split_positions.iter().filter(|s| centroid_x > **s).count();
+                (k, pi.d_transf, n_splits_left)
+            })
+            .filter(|(_, _, n)| *n > 0)
             .collect_vec();
 
-        for (pik, dtransf) in items_to_shift {
+        for (pik, dtransf, n_splits_left) in items_to_shift {
             let existing_transf = dtransf.compose();
-            let new_transf = existing_transf.translate((delta, 0.0));
+            let new_transf = existing_transf.translate((delta_per_split * n_splits_left as f32, 0.0));
             self.move_item(pik, new_transf.decompose());
         }
 
         self.prob.change_strip_width(new_width);
 
-        //rebuild the collision tracker
-        self.ct = CollisionTracker::new(&self.prob.layout);
+        //rebuild the collision tracker, carrying over the weights if configured
+        let prev_ct = std::mem::replace(&mut self.ct, CollisionTracker::new(&self.prob.layout, self.config.overlap_quantifier));
+        if let Some(decay) = self.config.weight_config.carry_over_decay {
+            //keys of moved items are already remapped in the previous tracker
+            self.ct.inherit_weights(&prev_ct, decay);
+        }
 
-        //rebuild the workers
+        //rebuild the workers, only their layout and tracker are copied, the instance remains shared
         self.workers.iter_mut().for_each(|opt| {
             *opt = SeparatorWorker {
-                instance: self.instance.clone(),
-                prob: self.prob.clone(),
+                instance: opt.instance.clone(),
+                layout: self.prob.layout.clone(),
                 ct: self.ct.clone(),
                 rng: SmallRng::seed_from_u64(self.rng.random()),
                 sample_config: self.config.sample_config.clone(),
+                // no-fit polygons do not depend on the strip width
+                nfp_cache: std::mem::take(&mut opt.nfp_cache),
+                // rebuilt on the next search, the container changed
+                fs_raster: FreeSpaceRaster::default(),
+                shape_cache: opt.shape_cache.clone(),
+                window: opt.window,
+                moved: vec![],
             };
         });
+        self.workers_synced = true;
         debug!("[SEP] changed strip width to {:.3}", new_width);
     }
 }
@@ -0,0 +1,63 @@
+use crate::config::ShrinkStepStrategy;
+
+/// Keeps track of the ratio by which the strip is shrunk during exploration.
+/// See [`ShrinkStepStrategy`] for the available schedules.
+#[derive(Debug, Clone)]
+pub struct ShrinkSchedule {
+    pub strategy: ShrinkStepStrategy,
+    step: f32,
+    n_conseq_fails: usize,
+    /// Whether a failure occurred since the last successful width (including the failures that caused a backoff)
+    failed_since_success: bool,
+}
+
+impl ShrinkSchedule {
+    pub fn new(strategy: ShrinkStepStrategy) -> Self {
+        let step = match strategy {
+            ShrinkStepStrategy::Fixed(step) => step,
+            ShrinkStepStrategy::Adaptive { init, .. } => init,
+        };
+        Self {
+            strategy,
+            step,
+            n_conseq_fails: 0,
+            failed_since_success: false,
+        }
+    }
+
+    /// The current shrink ratio, relative to the last feasible width
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Registers that the current width was successfully separated.
+    /// Returns the ratio by which the strip should be shrunk next.
+    pub fn report_success(&mut self) -> f32 {
+        if let ShrinkStepStrategy::Adaptive { max, growth, .. } = self.strategy && !self.failed_since_success {
+            // reached feasibility without a single failed attempt, be more aggressive
+            self.step = f32::min(self.step * growth, max);
+        }
+        self.n_conseq_fails = 0;
+        self.failed_since_success = false;
+        self.step
+    }
+
+    /// Registers a failed attempt to separate the current width.
+    /// Returns a new (smaller) ratio if the schedule decides to back off from the current width.
+    pub fn report_failure(&mut self) -> Option<f32> {
+        self.n_conseq_fails += 1;
+        self.failed_since_success = true;
+        match self.strategy {
+            ShrinkStepStrategy::Fixed(_) => None,
+            ShrinkStepStrategy::Adaptive { min, backoff, fails_before_backoff, .. } => {
+                if self.n_conseq_fails >= fails_before_backoff.get() && self.step > min {
+                    self.step = f32::max(self.step * backoff, min);
+                    self.n_conseq_fails = 0;
+                    Some(self.step)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
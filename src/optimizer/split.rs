@@ -0,0 +1,114 @@
+use itertools::Itertools;
+use jagua_rs::entities::Layout;
+use jagua_rs::geometry::primitives::SPolygon;
+use ordered_float::OrderedFloat;
+use rand::Rng;
+
+/// Number of horizontal bands in which the slack around a split position is measured
+const SLACK_N_BANDS: usize = 16;
+
+/// Determines where the strip is split when its width is changed.
+/// Items right of a split position are shifted by the change in width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitPositionStrategy {
+    /// Evenly spaced over the strip (the center for a single split)
+    Center,
+    /// Uniformly random positions
+    Random,
+    /// The candidate positions whose vertical line crosses the least item area
+    LeastCrossedArea { n_candidates: usize },
+    /// The candidate positions with the largest minimum horizontal gap between items on either side
+    MostSlack { n_candidates: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SplitConfig {
+    pub strategy: SplitPositionStrategy,
+    /// Number of positions over which the change in width is distributed
+    pub n_splits: usize,
+}
+
+/// Returns `config.n_splits` positions at which to split the strip of the layout
+pub fn split_positions(l: &Layout, config: &SplitConfig, rng: &mut impl Rng) -> Vec<f32> {
+    assert!(config.n_splits > 0, "at least one split position is required");
+    let bbox = l.container.outer_cd.bbox;
+    let (x_min, width) = (bbox.x_min, bbox.width());
+    let n_splits = config.n_splits;
+
+    match config.strategy {
+        SplitPositionStrategy::Center => (0..n_splits)
+            .map(|i| x_min + width * (i + 1) as f32 / (n_splits + 1) as f32)
+            .collect(),
+        SplitPositionStrategy::Random => (0..n_splits)
+            .map(|_| rng.random_range(x_min..x_min + width))
+            .collect(),
+        SplitPositionStrategy::LeastCrossedArea { n_candidates } => {
+            stratified_candidates(x_min, width, n_candidates.max(n_splits), rng)
+                .sorted_by_cached_key(|x| OrderedFloat(crossed_length(l, *x)))
+                .take(n_splits)
+                .collect()
+        }
+        SplitPositionStrategy::MostSlack { n_candidates } => {
+            stratified_candidates(x_min, width, n_candidates.max(n_splits), rng)
+                .sorted_by_cached_key(|x| OrderedFloat(-slack(l, *x)))
+                .take(n_splits)
+                .collect()
+        }
+    }
+}
+
+/// One random candidate in each of `n` equally wide intervals of the strip
+fn stratified_candidates(x_min: f32, width: f32, n: usize, rng: &mut impl Rng) -> impl Iterator<Item=f32> {
+    let interval = width / n as f32;
+    (0..n)
+        .map(|i| x_min + interval * (i as f32 + rng.random::<f32>()))
+        .collect_vec()
+        .into_iter()
+}
+
+/// Total length of the vertical line at `x` which lies inside placed items.
+/// Proportional to the item area removed by shrinking at `x`.
+fn crossed_length(l: &Layout, x: f32) -> f32 {
+    l.placed_items.values()
+        .filter(|pi| pi.shape.bbox.x_min < x && x < pi.shape.bbox.x_max)
+        .map(|pi| chord_length(&pi.shape, x))
+        .sum()
+}
+
+/// Length of the intersection between a polygon and the vertical line at `x`
+fn chord_length(shape: &SPolygon, x: f32) -> f32 {
+    let n = shape.vertices.len();
+    let ys = (0..n)
+        .map(|i| (shape.vertices[i], shape.vertices[(i + 1) % n]))
+        .filter(|(a, b)| (a.0 <= x) != (b.0 <= x))
+        .map(|(a, b)| a.1 + (x - a.0) * (b.1 - a.1) / (b.0 - a.0))
+        .sorted_by_key(|y| OrderedFloat(*y))
+        .collect_vec();
+
+    ys.chunks_exact(2).map(|c| c[1] - c[0]).sum()
+}
+
+/// Smallest horizontal gap, over all horizontal bands, between the items left and right of `x`
+/// (classified by their centroid, as in [`Separator::change_strip_width`](crate::optimizer::separator::Separator::change_strip_width)).
+/// Shrinking at `x` by less than the slack does not create overlap between bounding boxes.
+fn slack(l: &Layout, x: f32) -> f32 {
+    let bbox = l.container.outer_cd.bbox;
+    let band_height = bbox.height() / SLACK_N_BANDS as f32;
+
+    (0..SLACK_N_BANDS)
+        .map(|i| {
+            let (y_lo, y_hi) = (bbox.y_min + band_height * i as f32, bbox.y_min + band_height * (i + 1) as f32);
+            let in_band = l.placed_items.values()
+                .filter(|pi| pi.shape.bbox.y_min < y_hi && pi.shape.bbox.y_max > y_lo);
+
+            let (mut left_max, mut right_min) = (bbox.x_min, bbox.x_max);
+            for pi in in_band {
+                match pi.shape.centroid().0 > x {
+                    true => right_min = right_min.min(pi.shape.bbox.x_min),
+                    false => left_max = left_max.max(pi.shape.bbox.x_max),
+                }
+            }
+            right_min - left_max
+        })
+        .fold(f32::INFINITY, f32::min)
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+use crate::config::WindowConfig;
+use crate::optimizer::separator::Separator;
+
+/// Decomposes the strip into overlapping windows which are optimized one at a time.
+/// Every call to [`SlidingWindow::advance`] moves the window further along the strip (wrapping around at the end)
+/// and restricts the separator to it, see [`Separator::set_window`].
+/// The strip is shrunk at the center of the window, so the items outside of it are shifted rigidly and remain feasible:
+/// only the items in the window have to be separated, while the others stay fixed. After `(1 - global_ratio)` of the phase's time limit has passed,
+/// the decomposition is lifted and the remainder of the phase is global.
+pub struct SlidingWindow {
+    pub config: WindowConfig,
+    pub x_min: f32,
+    start: Instant,
+    windowed_duration: Duration,
+}
+
+impl SlidingWindow {
+    pub fn new(config: WindowConfig, time_limit: Duration) -> Self {
+        assert!(config.width_ratio > 0.0 && config.width_ratio <= 1.0);
+        assert!((0.0..1.0).contains(&config.overlap_ratio));
+        Self {
+            config,
+            x_min: 0.0,
+            start: Instant::now(),
+            windowed_duration: time_limit.mul_f32(1.0 - config.global_ratio),
+        }
+    }
+
+    /// Whether the phase is still decomposed into windows, or has moved on to its global pass
+    pub fn active(&self) -> bool {
+        self.start.elapsed() < self.windowed_duration
+    }
+
+    /// Slides the window to its next position and restricts the separator to it.
+    /// Returns the position at which to split the strip (the center of the window),
+    /// or `None` (and lifts any restriction) if the global pass has started.
+    pub fn advance(&mut self, sep: &mut Separator) -> Option<f32> {
+        if !self.active() {
+            sep.set_window(None);
+            return None;
+        }
+        let strip_width = sep.prob.strip_width();
+        let width = strip_width * self.config.width_ratio;
+        if self.x_min + width > strip_width {
+            //wrap around to the start of the strip
+            self.x_min = 0.0;
+        }
+        let (x_min, x_max) = (self.x_min, self.x_min + width);
+        self.x_min += width * (1.0 - self.config.overlap_ratio);
+
+        sep.set_window(Some((x_min, x_max)));
+        Some((x_min + x_max) / 2.0)
+    }
+}
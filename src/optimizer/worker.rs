@@ -1,43 +1,87 @@
 use crate::eval::sep_evaluator::SeparationEvaluator;
+use crate::eval::shape_cache::ShapeCache;
 use crate::quantify::tracker::CollisionTracker;
+use crate::eval::window_evaluator::WindowEvaluator;
 use crate::sample::search;
+use crate::sample::nfp_sampler::NFPCache;
+use crate::sample::free_space_sampler::FreeSpaceRaster;
 use crate::sample::search::SampleConfig;
 use crate::util::assertions::tracker_matches_layout;
 use crate::util::terminator::Terminator;
 use crate::FMT;
 use itertools::Itertools;
-use jagua_rs::entities::{Instance, PItemKey};
-use jagua_rs::probs::spp::entities::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use jagua_rs::entities::{Instance, Layout, PItemKey};
+use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
 use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::primitives::Rect;
 use log::debug;
 use rand::prelude::{SliceRandom, SmallRng};
 use std::iter::Sum;
 use std::ops::AddAssign;
+use std::sync::Arc;
 use tap::Tap;
 
 pub struct SeparatorWorker {
-    pub instance: SPInstance,
-    pub prob: SPProblem,
+    /// The instance, shared by the master and all workers
+    pub instance: Arc<SPInstance>,
+    /// The worker only holds its own layout, the width of the strip is managed by the master
+    pub layout: Layout,
     pub ct: CollisionTracker,
     pub rng: SmallRng,
     pub sample_config: SampleConfig,
+    pub nfp_cache: NFPCache,
+    /// Rasterization of the worker's layout for free-space sampling, updated incrementally
+    pub fs_raster: FreeSpaceRaster,
+    /// Shapes of the items pre-rotated to their allowed orientations, shared by all workers
+    pub shape_cache: Arc<ShapeCache>,
+    /// Horizontal range of the strip to which the worker is restricted, see [`Separator::set_window`](crate::optimizer::separator::Separator::set_window)
+    pub window: Option<(f32, f32)>,
+    /// Tracker indices of the items moved since the worker was last synced with the master
+    pub moved: Vec<usize>,
 }
 
 impl SeparatorWorker {
     pub fn load(&mut self, sol: &SPSolution, ct: &CollisionTracker) {
         // restores the state of the worker to the given solution and accompanying tracker
-        debug_assert!(sol.strip_width() == self.prob.strip_width());
-        self.prob.restore(sol);
+        self.layout.restore(&sol.layout_snapshot);
         self.ct = ct.clone();
+        self.moved.clear();
+    }
+
+    /// Syncs the worker with the master by only copying the items that changed since the last sync:
+    /// the items moved by the worker itself and the items in `master_changes`.
+    pub fn sync(&mut self, master_layout: &Layout, master_ct: &CollisionTracker, master_changes: &[usize]) {
+        let changed = self.moved.drain(..)
+            .chain(master_changes.iter().copied())
+            .unique()
+            .collect_vec();
+        sync_items(&self.instance, &mut self.layout, &mut self.ct, master_layout, master_ct, &changed);
     }
 
     pub fn move_colliding_items(&mut self) -> SepStats {
-        //collect all colliding items and shuffle them
-        let candidates = self.prob.layout.placed_items.keys()
-            .filter(|pk| self.ct.get_loss(*pk) > 0.0)
+        //collect all colliding items (in the window, if any) and shuffle them
+        let window = self.window;
+        let candidates = self.layout.placed_items.iter()
+            .filter(|(pk, pi)| {
+                let in_window = window.is_none_or(|(x_min, x_max)| (x_min..x_max).contains(&pi.shape.centroid().0));
+                in_window && self.ct.get_loss(*pk) > 0.0
+            })
+            .map(|(pk, _)| pk)
             .collect_vec()
             .tap_mut(|v| v.shuffle(&mut self.rng));
 
+        //restrict the container samples to the window
+        let container_bbox = self.layout.container.outer_cd.bbox;
+        let sample_bbox = match window {
+            Some((x_min, x_max)) => Rect {
+                x_min: x_min.max(container_bbox.x_min),
+                y_min: container_bbox.y_min,
+                x_max: x_max.min(container_bbox.x_max),
+                y_max: container_bbox.y_max,
+            },
+            None => container_bbox,
+        };
+
         let mut total_moves = 0;
         let mut total_evals = 0;
 
@@ -45,15 +89,17 @@ impl SeparatorWorker {
         for &pk in candidates.iter() {
             //check if the item is still colliding
             if self.ct.get_loss(pk) > 0.0 {
-                let item_id = self.prob.layout.placed_items[pk].item_id;
+                let item_id = self.layout.placed_items[pk].item_id;
                 let item = self.instance.item(item_id);
 
-                // create an evaluator to evaluate the samples during the search
-                let evaluator = SeparationEvaluator::new(&self.prob.layout, item, pk, &self.ct);
+                // create an evaluator to evaluate the samples during the search, items in a window are kept inside it
+                let evaluator = SeparationEvaluator::new(&self.layout, item, pk, &self.ct)
+                    .with_rotated_shapes(self.shape_cache.get(item_id));
+                let evaluator = WindowEvaluator::new(evaluator, item, window);
 
                 // search for a better position for the item
                 let (best_sample, n_evals) =
-                    search::search_placement(&self.prob.layout, item, Some(pk), evaluator, self.sample_config, &mut self.rng);
+                    search::search_placement_in(&self.layout, sample_bbox, item, Some(pk), evaluator, self.sample_config, &mut self.rng, &mut self.nfp_cache, &mut self.fs_raster);
 
                 let (new_dt, _eval) = best_sample.expect("search_placement should always return a sample");
 
@@ -67,29 +113,46 @@ impl SeparatorWorker {
     }
 
     pub fn move_item(&mut self, pk: PItemKey, d_transf: DTransformation) -> PItemKey {
-        debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
+        debug_assert!(tracker_matches_layout(&self.ct, &self.layout));
 
-        let item = self.instance.item(self.prob.layout.placed_items[pk].item_id);
+        let item_id = self.layout.placed_items[pk].item_id;
+        self.moved.push(self.ct.pk_idx_map[pk]);
 
         let (old_l, old_w_l) = (self.ct.get_loss(pk), self.ct.get_weighted_loss(pk));
 
-        //modify the problem, by removing the item and placing it in the new position
-        let old_placement = self.prob.remove_item(pk);
-        let new_placement = SPPlacement { d_transf, item_id: item.id };
-        let new_pk = self.prob.place_item(new_placement);
+        //modify the layout, by removing the item and placing it in the new position
+        let old_dt = self.layout.remove_item(pk).d_transf;
+        let new_pk = self.layout.place_item(self.instance.item(item_id), d_transf);
         //update the collision tracker to reflect the changes
-        self.ct.register_item_move(&self.prob.layout, pk, new_pk);
+        self.ct.register_item_move(&self.layout, pk, new_pk);
 
         let (new_l, new_w_l) = (self.ct.get_loss(new_pk), self.ct.get_weighted_loss(new_pk));
 
-        debug!("Moved {:?} (l: {}, wl: {}) to {:?} (l+1: {}, wl+1: {})", old_placement, FMT().fmt2(old_l), FMT().fmt2(old_w_l), new_placement, FMT().fmt2(new_l), FMT().fmt2(new_w_l));
+        debug!("Moved item {} from [{}] (l: {}, wl: {}) to [{}] (l+1: {}, wl+1: {})", item_id, old_dt, FMT().fmt2(old_l), FMT().fmt2(old_w_l), d_transf, FMT().fmt2(new_l), FMT().fmt2(new_w_l));
         debug_assert!(new_w_l <= old_w_l * 1.001, "weighted loss should never increase: {} > {}", FMT().fmt2(old_w_l), FMT().fmt2(new_w_l));
-        debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
+        debug_assert!(tracker_matches_layout(&self.ct, &self.layout));
 
         new_pk
     }
 }
 
+/// Brings the items at tracker indices `idxs` (and their tracker rows) in line with a source layout and tracker.
+/// Both trackers should share the same indices and all other items should already be identical.
+/// Every item is removed and placed again, so the number of placed copies of each item is unaffected.
+pub fn sync_items(instance: &SPInstance, layout: &mut Layout, ct: &mut CollisionTracker, src_layout: &Layout, src_ct: &CollisionTracker, idxs: &[usize]) {
+    for &idx in idxs {
+        let pk = ct.idx_pk_map[idx];
+        let src_pi = &src_layout.placed_items[src_ct.idx_pk_map[idx]];
+        if layout.placed_items[pk].d_transf != src_pi.d_transf {
+            layout.remove_item(pk);
+            let new_pk = layout.place_item(instance.item(src_pi.item_id), src_pi.d_transf);
+            ct.remap_key(pk, new_pk);
+        }
+    }
+    ct.copy_rows_from(src_ct, idxs);
+    debug_assert!(tracker_matches_layout(ct, layout));
+}
+
 pub struct SepStats {
     pub total_moves: usize,
     pub total_evals: usize,
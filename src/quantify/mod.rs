@@ -1,14 +1,41 @@
 use jagua_rs::geometry::geo_traits::DistanceTo;
 use jagua_rs::geometry::primitives::{Rect, SPolygon};
 use crate::consts::OVERLAP_PROXY_EPSILON_DIAM_RATIO;
-use crate::quantify::overlap_proxy::overlap_area_proxy;
+use crate::quantify::overlap_area::overlap_area;
+use crate::quantify::overlap_proxy::{overlap_area_proxy, overlap_area_proxy_pruned};
 
+pub mod overlap_area;
 pub mod overlap_proxy;
-mod pair_matrix;
+mod pair_map;
 pub mod tracker;
+pub mod weights;
 #[cfg(feature = "simd")]
 pub mod simd;
 
+/// Method used to quantify the overlap between two colliding items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapQuantifier {
+    /// Fast proxy based on the penetration depth of the poles of both items, see [`overlap_area_proxy`]
+    PoleProxy,
+    /// Same as [`OverlapQuantifier::PoleProxy`], but skips pairs of poles which are far apart, see [`overlap_area_proxy_pruned`].
+    /// Faster, at the cost of slightly lower losses than the exhaustive proxy.
+    PrunedPoleProxy,
+    /// Exact intersection area of both items, see [`overlap_area`].
+    /// Slower, but not sensitive to the pole configuration of thin or very concave items.
+    ExactArea,
+}
+
+impl OverlapQuantifier {
+    #[inline(always)]
+    pub fn quantify_poly_poly(&self, s1: &SPolygon, s2: &SPolygon) -> f32 {
+        match self {
+            OverlapQuantifier::PoleProxy => quantify_collision_poly_poly(s1, s2),
+            OverlapQuantifier::PrunedPoleProxy => quantify_collision_poly_poly_pruned(s1, s2),
+            OverlapQuantifier::ExactArea => quantify_collision_poly_poly_exact(s1, s2),
+        }
+    }
+}
+
 /// Quantifies a collision between two simple polygons.
 #[inline(always)]
 pub fn quantify_collision_poly_poly(s1: &SPolygon, s2: &SPolygon) -> f32 {
@@ -23,6 +50,35 @@ pub fn quantify_collision_poly_poly(s1: &SPolygon, s2: &SPolygon) -> f32 {
     overlap_proxy.sqrt() * penalty
 }
 
+/// Same as [`quantify_collision_poly_poly`], but with the pruned proxy, see [`overlap_area_proxy_pruned`].
+#[inline(always)]
+pub fn quantify_collision_poly_poly_pruned(s1: &SPolygon, s2: &SPolygon) -> f32 {
+    let epsilon = f32::max(s1.diameter, s2.diameter) * OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+
+    let overlap_proxy = overlap_area_proxy_pruned(&s1.surrogate(), &s2.surrogate(), epsilon) + epsilon.powi(2);
+
+    debug_assert!(overlap_proxy.is_normal());
+
+    let penalty = calc_shape_penalty(s1, s2);
+
+    overlap_proxy.sqrt() * penalty
+}
+
+/// Quantifies a collision between two simple polygons using their exact intersection area.
+/// Mirrors [`quantify_collision_poly_poly`], but with the exact area instead of the pole-based proxy.
+pub fn quantify_collision_poly_poly_exact(s1: &SPolygon, s2: &SPolygon) -> f32 {
+    let epsilon = f32::max(s1.diameter, s2.diameter) * OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+
+    // collisions with (near) zero intersection area still need a strictly positive loss
+    let overlap = overlap_area(s1, s2) + epsilon.powi(2);
+
+    debug_assert!(overlap.is_normal());
+
+    let penalty = calc_shape_penalty(s1, s2);
+
+    overlap.sqrt() * penalty
+}
+
 pub fn calc_shape_penalty(s1: &SPolygon, s2: &SPolygon) -> f32 {
     let p1 = f32::sqrt(s1.surrogate().convex_hull_area);
     let p2 = f32::sqrt(s2.surrogate().convex_hull_area);
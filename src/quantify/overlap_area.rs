@@ -0,0 +1,132 @@
+use jagua_rs::geometry::primitives::SPolygon;
+
+type Pt = (f64, f64);
+
+/// Maximum number of vertices of the intersection of two triangles (+ margin for clipping)
+const CLIP_BUFFER_SIZE: usize = 12;
+
+/// Calculates the exact area of the intersection between two simple polygons.
+/// Both polygons are decomposed into a fan of signed triangles (originating from their first vertex).
+/// Since the indicator function of a simple polygon equals the signed sum of its fan triangles,
+/// the intersection area is the signed sum of the areas of all pairwise (convex) triangle intersections.
+pub fn overlap_area(s1: &SPolygon, s2: &SPolygon) -> f32 {
+    let fan_1 = signed_fan(s1);
+    let fan_2 = signed_fan(s2);
+
+    let mut total_area = 0.0;
+    for t1 in fan_1.iter() {
+        for t2 in fan_2.iter().filter(|t2| t1.bbox_overlaps(t2)) {
+            total_area += t1.sign * t2.sign * triangle_intersection_area(&t1.vertices, &t2.vertices);
+        }
+    }
+    // guard against negative values caused by rounding errors
+    f64::max(total_area, 0.0) as f32
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SignedTriangle {
+    /// Vertices in counterclockwise order
+    vertices: [Pt; 3],
+    /// +1.0 or -1.0, depending on the orientation of the triangle relative to the polygon
+    sign: f64,
+    bbox: (Pt, Pt),
+}
+
+impl SignedTriangle {
+    fn new(vertices: [Pt; 3], sign: f64) -> Self {
+        let x_min = vertices.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let y_min = vertices.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let x_max = vertices.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+        let y_max = vertices.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        Self {
+            vertices,
+            sign,
+            bbox: ((x_min, y_min), (x_max, y_max)),
+        }
+    }
+
+    fn bbox_overlaps(&self, other: &SignedTriangle) -> bool {
+        let ((a_x_min, a_y_min), (a_x_max, a_y_max)) = self.bbox;
+        let ((b_x_min, b_y_min), (b_x_max, b_y_max)) = other.bbox;
+        a_x_min < b_x_max && b_x_min < a_x_max && a_y_min < b_y_max && b_y_min < a_y_max
+    }
+}
+
+fn signed_fan(s: &SPolygon) -> Vec<SignedTriangle> {
+    let vertices = s.vertices.iter().map(|p| (p.0 as f64, p.1 as f64)).collect::<Vec<_>>();
+    let origin = vertices[0];
+
+    // the signs of the fan are relative to the orientation of the polygon itself
+    let orientation = vertices.windows(2).skip(1)
+        .map(|w| cross(origin, w[0], w[1]))
+        .sum::<f64>()
+        .signum();
+
+    vertices.windows(2).skip(1)
+        .filter_map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let signed_area = cross(origin, a, b);
+            if signed_area > 0.0 {
+                Some(SignedTriangle::new([origin, a, b], orientation))
+            } else if signed_area < 0.0 {
+                Some(SignedTriangle::new([origin, b, a], -orientation))
+            } else {
+                None // degenerate triangle, does not contribute
+            }
+        })
+        .collect()
+}
+
+/// Area of the intersection of two counterclockwise triangles, using Sutherland-Hodgman clipping
+fn triangle_intersection_area(subject: &[Pt; 3], clip: &[Pt; 3]) -> f64 {
+    let mut poly = [(0.0, 0.0); CLIP_BUFFER_SIZE];
+    let mut n = 3;
+    poly[..3].copy_from_slice(subject);
+
+    let mut buffer = [(0.0, 0.0); CLIP_BUFFER_SIZE];
+    for i in 0..3 {
+        let (c1, c2) = (clip[i], clip[(i + 1) % 3]);
+        let mut m = 0;
+        for j in 0..n {
+            let (p, q) = (poly[j], poly[(j + 1) % n]);
+            let (p_in, q_in) = (cross(c1, c2, p) >= 0.0, cross(c1, c2, q) >= 0.0);
+            if p_in {
+                buffer[m] = p;
+                m += 1;
+            }
+            if p_in != q_in {
+                buffer[m] = line_intersection(p, q, c1, c2);
+                m += 1;
+            }
+        }
+        if m < 3 {
+            return 0.0;
+        }
+        poly[..m].copy_from_slice(&buffer[..m]);
+        n = m;
+    }
+
+    let area = (0..n)
+        .map(|j| {
+            let (p, q) = (poly[j], poly[(j + 1) % n]);
+            p.0 * q.1 - q.0 * p.1
+        })
+        .sum::<f64>() / 2.0;
+
+    f64::max(area, 0.0)
+}
+
+/// Z-component of the cross product of (b - a) and (c - a), positive if c lies left of a -> b
+#[inline(always)]
+fn cross(a: Pt, b: Pt, c: Pt) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Intersection of segment p -> q with the infinite line through a and b (assumes they intersect)
+#[inline(always)]
+fn line_intersection(p: Pt, q: Pt, a: Pt, b: Pt) -> Pt {
+    let d_p = cross(a, b, p);
+    let d_q = cross(a, b, q);
+    let t = d_p / (d_p - d_q);
+    (p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1))
+}
@@ -1,26 +1,81 @@
 use std::f32::consts::PI;
 use jagua_rs::geometry::fail_fast::SPSurrogate;
 use jagua_rs::geometry::geo_traits::DistanceTo;
+use jagua_rs::geometry::primitives::{Circle, Rect};
+use crate::consts::OVERLAP_PROXY_PRUNE_EPSILON_RATIO;
 
-/// Calculates a proxy for the overlap area between two simple polygons (using poles).
+/// Calculates a proxy for the overlap area between two simple polygons, evaluating all pairs of poles.
 #[inline(always)]
-pub fn overlap_area_proxy<'a>(sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32) -> f32 {
+pub fn overlap_area_proxy(sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32) -> f32 {
     let mut total_overlap = 0.0;
     for p1 in &sp1.poles {
         for p2 in &sp2.poles {
-            // Penetration depth between the two poles (circles)
-            let pd = (p1.radius + p2.radius) - p1.center.distance_to(&p2.center);
+            total_overlap += pole_pair_overlap(p1, p2, epsilon);
+        }
+    }
+    total_overlap *= PI;
+    debug_assert!(total_overlap.is_normal());
 
-            let pd_decay = match pd >= epsilon {
-                true => pd,
-                false => epsilon.powi(2) / (-pd + 2.0 * epsilon),
-            };
+    total_overlap
+}
 
-            total_overlap += pd_decay * f32::min(p1.radius, p2.radius);
+/// Same as [`overlap_area_proxy`], but pole pairs that can only contribute a negligible decay term are pruned in two levels:
+/// poles of `sp1` that do not reach the bounding box of the poles of `sp2` are skipped entirely,
+/// the remaining pairs are skipped if they are more than `margin` apart (without computing their distance).
+/// The result is slightly lower than the exhaustive proxy, see [`OVERLAP_PROXY_PRUNE_EPSILON_RATIO`].
+/// The SIMD version (feature `simd`) prunes exactly the same pairs.
+#[inline(always)]
+pub fn overlap_area_proxy_pruned(sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32) -> f32 {
+    let margin = epsilon * OVERLAP_PROXY_PRUNE_EPSILON_RATIO;
+    let bbox2 = poles_bbox(&sp2.poles);
+
+    let mut total_overlap = 0.0;
+    for p1 in sp1.poles.iter().filter(|p1| reaches(p1, bbox2, margin)) {
+        for p2 in &sp2.poles {
+            let reach = p1.radius + p2.radius + margin;
+            let (dx, dy) = (p1.center.0 - p2.center.0, p1.center.1 - p2.center.1);
+            if dx * dx + dy * dy > reach * reach {
+                continue;
+            }
+            total_overlap += pole_pair_overlap(p1, p2, epsilon);
         }
     }
     total_overlap *= PI;
-    debug_assert!(total_overlap.is_normal());
-    
+    debug_assert!(total_overlap.is_finite() && total_overlap >= 0.0);
+
     total_overlap
-}
\ No newline at end of file
+}
+
+#[inline(always)]
+fn pole_pair_overlap(p1: &Circle, p2: &Circle, epsilon: f32) -> f32 {
+    // Penetration depth between the two poles (circles)
+    let pd = (p1.radius + p2.radius) - p1.center.distance_to(&p2.center);
+
+    let pd_decay = match pd >= epsilon {
+        true => pd,
+        false => epsilon.powi(2) / (-pd + 2.0 * epsilon),
+    };
+
+    pd_decay * f32::min(p1.radius, p2.radius)
+}
+
+/// Bounding box of a set of poles
+pub(crate) fn poles_bbox(poles: &[Circle]) -> Rect {
+    poles.iter().fold(
+        Rect { x_min: f32::INFINITY, y_min: f32::INFINITY, x_max: f32::NEG_INFINITY, y_max: f32::NEG_INFINITY },
+        |bb, p| Rect {
+            x_min: bb.x_min.min(p.center.0 - p.radius),
+            y_min: bb.y_min.min(p.center.1 - p.radius),
+            x_max: bb.x_max.max(p.center.0 + p.radius),
+            y_max: bb.y_max.max(p.center.1 + p.radius),
+        },
+    )
+}
+
+/// Whether a pole comes within `margin` of a bounding box
+#[inline(always)]
+pub(crate) fn reaches(p: &Circle, bbox: Rect, margin: f32) -> bool {
+    let reach = p.radius + margin;
+    p.center.0 + reach >= bbox.x_min && p.center.0 - reach <= bbox.x_max &&
+        p.center.1 + reach >= bbox.y_min && p.center.1 - reach <= bbox.y_max
+}
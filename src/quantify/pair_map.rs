@@ -0,0 +1,135 @@
+use crate::quantify::tracker::CTEntry;
+
+// sparse symmetric map of pair-wise collision loss and weights
+// supporting data structure for the `CollisionTracker`
+// only entries with a non-zero loss or a non-default weight are stored,
+// the sums over every row are maintained incrementally
+#[derive(Debug, Clone)]
+pub struct PairMap {
+    pub size: usize,
+    rows: Vec<Vec<(usize, CTEntry)>>,
+    sums: Vec<RowSums>,
+}
+
+impl PairMap {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            rows: vec![vec![]; size],
+            sums: vec![RowSums::default(); size],
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> CTEntry {
+        debug_assert!(row < self.size && col < self.size);
+        self.rows[row].iter()
+            .find(|(c, _)| *c == col)
+            .map(|(_, e)| *e)
+            .unwrap_or(CTEntry::DEFAULT)
+    }
+
+    /// All stored entries of a row
+    pub fn row(&self, row: usize) -> impl Iterator<Item=(usize, CTEntry)> + '_ {
+        self.rows[row].iter().copied()
+    }
+
+    pub fn sums(&self, row: usize) -> RowSums {
+        self.sums[row]
+    }
+
+    /// All stored entries, every pair is visited once (with `row < col`)
+    pub fn iter(&self) -> impl Iterator<Item=(usize, usize, CTEntry)> + '_ {
+        self.rows.iter().enumerate()
+            .flat_map(|(row, entries)| entries.iter().map(move |(col, e)| (row, *col, *e)))
+            .filter(|(row, col, _)| row < col)
+    }
+
+    pub fn set_loss(&mut self, row: usize, col: usize, loss: f32) {
+        self.update(row, col, |e| e.loss = loss);
+    }
+
+    pub fn set_weight(&mut self, row: usize, col: usize, weight: f32) {
+        self.update(row, col, |e| e.weight = weight);
+    }
+
+    /// Sets the loss of all pairs involving `row` to zero
+    pub fn clear_losses(&mut self, row: usize) {
+        let colliding = self.rows[row].iter()
+            .filter(|(_, e)| e.loss > 0.0)
+            .map(|(col, _)| *col)
+            .collect::<Vec<_>>();
+        for col in colliding {
+            self.set_loss(row, col, 0.0);
+        }
+    }
+
+    /// Makes a row (and the corresponding column) identical to the one of another map of the same size
+    pub fn copy_row_from(&mut self, other: &PairMap, row: usize) {
+        let stale = self.rows[row].iter()
+            .map(|(col, _)| *col)
+            .filter(|col| !other.rows[row].iter().any(|(o_col, _)| o_col == col))
+            .collect::<Vec<_>>();
+        for col in stale {
+            self.update(row, col, |e| *e = CTEntry::DEFAULT);
+        }
+        for &(col, o_e) in other.rows[row].iter() {
+            self.update(row, col, |e| *e = o_e);
+        }
+    }
+
+    /// Replaces the weight of every stored entry. Entries which are not stored are left at the default weight,
+    /// so `new_weight` should map a default entry to the default weight.
+    pub fn update_weights(&mut self, mut new_weight: impl FnMut(CTEntry) -> f32) {
+        let pairs = self.iter().collect::<Vec<_>>();
+        for (row, col, e) in pairs {
+            self.set_weight(row, col, new_weight(e));
+        }
+    }
+
+    fn update(&mut self, row: usize, col: usize, modify: impl Fn(&mut CTEntry)) {
+        debug_assert!(row != col, "an item cannot collide with itself");
+        let old = self.get(row, col);
+        let mut new = old;
+        modify(&mut new);
+
+        //keep both halves of the map in sync
+        for (r, c) in [(row, col), (col, row)] {
+            let entries = &mut self.rows[r];
+            match (entries.iter().position(|(c_, _)| *c_ == c), new.is_default()) {
+                (Some(i), true) => { entries.swap_remove(i); }
+                (Some(i), false) => entries[i].1 = new,
+                (None, false) => entries.push((c, new)),
+                (None, true) => {}
+            }
+            self.sums[r].replace(old, new, entries.is_empty());
+        }
+    }
+}
+
+/// Sums over all entries of a row of the [`PairMap`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RowSums {
+    pub loss: f32,
+    pub weighted_loss: f32,
+    /// Sum of all weights in excess of the default weight (1.0)
+    pub excess_weight: f32,
+    pub n_colliding: usize,
+}
+
+impl RowSums {
+    fn replace(&mut self, old: CTEntry, new: CTEntry, row_empty: bool) {
+        self.loss += new.loss - old.loss;
+        self.weighted_loss += new.weighted_loss() - old.weighted_loss();
+        self.excess_weight += new.weight - old.weight;
+        self.n_colliding = self.n_colliding + (new.loss > 0.0) as usize - (old.loss > 0.0) as usize;
+
+        //snap to exact zeros to prevent floating point drift from leaving residual loss
+        if self.n_colliding == 0 {
+            self.loss = 0.0;
+            self.weighted_loss = 0.0;
+        }
+        if row_empty {
+            self.excess_weight = 0.0;
+        }
+    }
+}
@@ -1,94 +1,279 @@
-use crate::quantify::overlap_proxy::overlap_area_proxy;
+use crate::consts::OVERLAP_PROXY_PRUNE_EPSILON_RATIO;
+use crate::quantify::overlap_proxy::{overlap_area_proxy, overlap_area_proxy_pruned, poles_bbox, reaches};
 use crate::quantify::simd::circles_soa::CirclesSoA;
 use float_cmp::approx_eq;
-use std::simd::Simd;
 use jagua_rs::geometry::fail_fast::SPSurrogate;
-use jagua_rs::geometry::geo_traits::DistanceTo;
-use jagua_rs::geometry::primitives::{Circle, Point};
 use std::f32::consts::PI;
+use std::sync::OnceLock;
 
-/// Width of the SIMD vector
-const SIMD_WIDTH: usize = 4;
-
-#[allow(non_camel_case_types)]
-type f32xN = Simd<f32,SIMD_WIDTH>;
-
-/// SIMD version of [`poles_overlap_area_proxy`] with configurable vector width.
-/// `p2` should match the poles of `sp2`.
-#[inline(always)]
-pub fn poles_overlap_area_proxy_simd(sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32, p2: &CirclesSoA) -> f32 {
-    use std::simd::prelude::{SimdFloat, SimdPartialOrd};
-    use std::simd::StdFloat;
+/// Instruction set used by the SIMD kernels, selected at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SimdLevel {
+    /// No SIMD, falls back to [`overlap_area_proxy`] or [`overlap_area_proxy_pruned`]
+    Scalar,
+    /// 4 lanes
+    Sse,
+    /// 8 lanes
+    Avx2,
+    /// 16 lanes
+    Avx512,
+}
 
-    let e_n = f32xN::splat(epsilon);
-    let e_sq_n = f32xN::splat(epsilon * epsilon);
-    let two_e_n = f32xN::splat(2.0 * epsilon);
+impl SimdLevel {
+    pub const ALL: [SimdLevel; 4] = [SimdLevel::Scalar, SimdLevel::Sse, SimdLevel::Avx2, SimdLevel::Avx512];
 
-    let mut total_overlap = 0.0;
-    for p1 in sp1.poles.iter() {
-        // common values for all chunks
-        let r1 = p1.radius;
-        let x1_n = f32xN::splat(p1.center.x());
-        let y1_n = f32xN::splat(p1.center.y());
-        let r1_n = f32xN::splat(r1);
+    /// Widest level supported by the CPU, detected once
+    pub fn detected() -> SimdLevel {
+        static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+        *LEVEL.get_or_init(|| {
+            SimdLevel::ALL.into_iter()
+                .filter(|l| l.is_supported())
+                .max()
+                .unwrap_or(SimdLevel::Scalar)
+        })
+    }
 
-        // process complete chunks with SIMD
-        let chunks = p2.x.len() / SIMD_WIDTH;
+    pub fn is_supported(&self) -> bool {
+        match self {
+            SimdLevel::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Sse => is_x86_feature_detected!("sse"),
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx512 => is_x86_feature_detected!("avx512f"),
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
 
-        for chunk in 0..chunks {
-            let idx = chunk * SIMD_WIDTH;
+    pub fn lanes(&self) -> usize {
+        match self {
+            SimdLevel::Scalar => 1,
+            SimdLevel::Sse => 4,
+            SimdLevel::Avx2 => 8,
+            SimdLevel::Avx512 => 16,
+        }
+    }
+}
 
-            // load the next N elements from p2
-            let x2 = f32xN::from_slice(&p2.x[idx..idx + SIMD_WIDTH]);
-            let y2 = f32xN::from_slice(&p2.y[idx..idx + SIMD_WIDTH]);
-            let r2 = f32xN::from_slice(&p2.r[idx..idx + SIMD_WIDTH]);
+/// SIMD version of [`overlap_area_proxy`] (or [`overlap_area_proxy_pruned`] if `prune`), using the widest instruction set supported by the CPU.
+/// Evaluates the same pole pairs as the scalar version, so both yield the same loss up to summation order.
+/// `p2` should match the poles of `sp2`.
+#[inline(always)]
+pub fn poles_overlap_area_proxy_simd(sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32, p2: &CirclesSoA, prune: bool) -> f32 {
+    poles_overlap_area_proxy_with(SimdLevel::detected(), sp1, sp2, epsilon, p2, prune)
+}
 
-            // calculate pd
-            let dx = x1_n - x2;
-            let dy = y1_n - y2;
+/// [`poles_overlap_area_proxy_simd`] with an explicit [`SimdLevel`].
+/// Panics if the level is not supported by the CPU.
+pub fn poles_overlap_area_proxy_with(level: SimdLevel, sp1: &SPSurrogate, sp2: &SPSurrogate, epsilon: f32, p2: &CirclesSoA, prune: bool) -> f32 {
+    assert!(level.is_supported(), "{:?} is not supported by this CPU", level);
+    debug_assert!(p2.x.len() == sp2.poles.len() && p2.y.len() == p2.x.len() && p2.r.len() == p2.x.len());
 
-            let pd = r1_n + r2 - (dx * dx + dy * dy).sqrt();
+    let scalar_proxy = match prune {
+        true => overlap_area_proxy_pruned,
+        false => overlap_area_proxy,
+    };
 
-            // calculate pd_decay
-            let pd_mask = pd.simd_ge(e_n);
-            let decay_values = e_sq_n / (-pd + two_e_n);
-            let pd_decay = pd_mask.select(pd, decay_values);
+    let mut total_overlap = match level {
+        SimdLevel::Scalar => return scalar_proxy(sp1, sp2, epsilon),
+        #[cfg(target_arch = "x86_64")]
+        _ => {
+            //without pruning, every pair is within reach
+            let margin = match prune {
+                true => epsilon * OVERLAP_PROXY_PRUNE_EPSILON_RATIO,
+                false => f32::INFINITY,
+            };
+            let bbox2 = poles_bbox(&sp2.poles);
+            sp1.poles.iter()
+                .filter(|p1| reaches(p1, bbox2, margin))
+                .map(|p1| {
+                    let (x1, y1, r1) = (p1.center.0, p1.center.1, p1.radius);
+                    // SAFETY: the required target features were detected above
+                    unsafe {
+                        match level {
+                            SimdLevel::Sse => x86::pole_overlap_sse(x1, y1, r1, p2, epsilon, margin),
+                            SimdLevel::Avx2 => x86::pole_overlap_avx2(x1, y1, r1, p2, epsilon, margin),
+                            _ => x86::pole_overlap_avx512(x1, y1, r1, p2, epsilon, margin),
+                        }
+                    }
+                })
+                .sum::<f32>()
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        _ => unreachable!(),
+    };
 
-            // calculate min radius
-            let min_r = r1_n.simd_min(r2);
+    total_overlap *= PI;
 
-            total_overlap += (pd_decay * min_r).reduce_sum();
-        }
+    debug_assert!(
+        approx_eq!(f32, total_overlap, scalar_proxy(sp1, sp2, epsilon),
+                 epsilon = total_overlap * 1e-3),
+                  "SIMD and SEQ results do not match: {} vs {}", total_overlap,
+                  scalar_proxy(sp1, sp2, epsilon)
+    );
 
-        // process remaining elements with scalar operations
-        let remaining_idx = chunks * SIMD_WIDTH;
-        for j in remaining_idx..p2.x.len() {
-            let p2 = Circle { 
-                center : Point(p2.x[j], p2.y[j]), 
-                radius: p2.r[j]
-            };
+    debug_assert!(total_overlap.is_finite() && total_overlap >= 0.0);
+    total_overlap
+}
 
+/// Overlap of a single pole with the poles in `p2` from index `start` onwards, without SIMD (and without the factor PI).
+/// Pairs more than `margin` apart are skipped, like in [`overlap_area_proxy_pruned`].
+#[inline(always)]
+fn pole_overlap_scalar(x1: f32, y1: f32, r1: f32, p2: &CirclesSoA, epsilon: f32, margin: f32, start: usize) -> f32 {
+    (start..p2.x.len())
+        .filter(|&j| {
+            let reach = r1 + p2.r[j] + margin;
+            let (dx, dy) = (x1 - p2.x[j], y1 - p2.y[j]);
+            dx * dx + dy * dy <= reach * reach
+        })
+        .map(|j| {
             // Penetration depth between the two poles (circles)
-            let pd = (p1.radius + p2.radius) - p1.center.distance_to(&p2.center);
+            let pd = (r1 + p2.r[j]) - ((x1 - p2.x[j]).powi(2) + (y1 - p2.y[j]).powi(2)).sqrt();
 
             let pd_decay = match pd >= epsilon {
                 true => pd,
                 false => epsilon.powi(2) / (-pd + 2.0 * epsilon),
             };
 
-            total_overlap += pd_decay * f32::min(p1.radius, p2.radius);
+            pd_decay * f32::min(r1, p2.r[j])
+        })
+        .sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::pole_overlap_scalar;
+    use crate::quantify::simd::circles_soa::CirclesSoA;
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "sse")]
+    pub unsafe fn pole_overlap_sse(x1: f32, y1: f32, r1: f32, p2: &CirclesSoA, epsilon: f32, margin: f32) -> f32 {
+        const LANES: usize = 4;
+        let chunks = p2.x.len() / LANES;
+        let mut lanes = [0.0f32; LANES];
+        unsafe {
+            let (x1_n, y1_n, r1_n) = (_mm_set1_ps(x1), _mm_set1_ps(y1), _mm_set1_ps(r1));
+            let e_n = _mm_set1_ps(epsilon);
+            let e_sq_n = _mm_set1_ps(epsilon * epsilon);
+            let two_e_n = _mm_set1_ps(2.0 * epsilon);
+            let margin_n = _mm_set1_ps(margin);
+
+            let mut acc = _mm_setzero_ps();
+            for chunk in 0..chunks {
+                let idx = chunk * LANES;
+                let x2 = _mm_loadu_ps(p2.x.as_ptr().add(idx));
+                let y2 = _mm_loadu_ps(p2.y.as_ptr().add(idx));
+                let r2 = _mm_loadu_ps(p2.r.as_ptr().add(idx));
+
+                // calculate pd
+                let dx = _mm_sub_ps(x1_n, x2);
+                let dy = _mm_sub_ps(y1_n, y2);
+                let d_sq = _mm_add_ps(_mm_mul_ps(dx, dx), _mm_mul_ps(dy, dy));
+                let dist = _mm_sqrt_ps(d_sq);
+                let pd = _mm_sub_ps(_mm_add_ps(r1_n, r2), dist);
+
+                // pairs out of reach are pruned, as in the scalar version
+                let reach = _mm_add_ps(_mm_add_ps(r1_n, r2), margin_n);
+
+                // calculate pd_decay
+                let decay_values = _mm_div_ps(e_sq_n, _mm_sub_ps(two_e_n, pd));
+                let pd_mask = _mm_cmpge_ps(pd, e_n);
+                let pd_decay = _mm_or_ps(_mm_and_ps(pd_mask, pd), _mm_andnot_ps(pd_mask, decay_values));
+
+                let reach_mask = _mm_cmple_ps(d_sq, _mm_mul_ps(reach, reach));
+                let contrib = _mm_mul_ps(pd_decay, _mm_min_ps(r1_n, r2));
+                acc = _mm_add_ps(acc, _mm_and_ps(reach_mask, contrib));
+            }
+            _mm_storeu_ps(lanes.as_mut_ptr(), acc);
         }
+        lanes.iter().sum::<f32>() + pole_overlap_scalar(x1, y1, r1, p2, epsilon, margin, chunks * LANES)
     }
-    
-    total_overlap *= PI;
 
-    debug_assert!(
-        approx_eq!(f32, total_overlap, overlap_area_proxy(sp1, sp2, epsilon),
-                 epsilon = total_overlap * 1e-3),
-                  "SIMD and SEQ results do not match: {} vs {}", total_overlap,
-                  overlap_area_proxy(sp1, sp2, epsilon)
-    );
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn pole_overlap_avx2(x1: f32, y1: f32, r1: f32, p2: &CirclesSoA, epsilon: f32, margin: f32) -> f32 {
+        const LANES: usize = 8;
+        let chunks = p2.x.len() / LANES;
+        let mut lanes = [0.0f32; LANES];
+        unsafe {
+            let (x1_n, y1_n, r1_n) = (_mm256_set1_ps(x1), _mm256_set1_ps(y1), _mm256_set1_ps(r1));
+            let e_n = _mm256_set1_ps(epsilon);
+            let e_sq_n = _mm256_set1_ps(epsilon * epsilon);
+            let two_e_n = _mm256_set1_ps(2.0 * epsilon);
+            let margin_n = _mm256_set1_ps(margin);
 
-    debug_assert!(total_overlap.is_normal());
-    total_overlap
+            let mut acc = _mm256_setzero_ps();
+            for chunk in 0..chunks {
+                let idx = chunk * LANES;
+                let x2 = _mm256_loadu_ps(p2.x.as_ptr().add(idx));
+                let y2 = _mm256_loadu_ps(p2.y.as_ptr().add(idx));
+                let r2 = _mm256_loadu_ps(p2.r.as_ptr().add(idx));
+
+                // calculate pd
+                let dx = _mm256_sub_ps(x1_n, x2);
+                let dy = _mm256_sub_ps(y1_n, y2);
+                let d_sq = _mm256_add_ps(_mm256_mul_ps(dx, dx), _mm256_mul_ps(dy, dy));
+                let dist = _mm256_sqrt_ps(d_sq);
+                let pd = _mm256_sub_ps(_mm256_add_ps(r1_n, r2), dist);
+
+                // pairs out of reach are pruned, as in the scalar version
+                let reach = _mm256_add_ps(_mm256_add_ps(r1_n, r2), margin_n);
+
+                // calculate pd_decay
+                let decay_values = _mm256_div_ps(e_sq_n, _mm256_sub_ps(two_e_n, pd));
+                let pd_mask = _mm256_cmp_ps::<_CMP_GE_OQ>(pd, e_n);
+                let pd_decay = _mm256_blendv_ps(decay_values, pd, pd_mask);
+
+                let reach_mask = _mm256_cmp_ps::<_CMP_LE_OQ>(d_sq, _mm256_mul_ps(reach, reach));
+                let contrib = _mm256_mul_ps(pd_decay, _mm256_min_ps(r1_n, r2));
+                acc = _mm256_add_ps(acc, _mm256_and_ps(reach_mask, contrib));
+            }
+            _mm256_storeu_ps(lanes.as_mut_ptr(), acc);
+        }
+        lanes.iter().sum::<f32>() + pole_overlap_scalar(x1, y1, r1, p2, epsilon, margin, chunks * LANES)
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn pole_overlap_avx512(x1: f32, y1: f32, r1: f32, p2: &CirclesSoA, epsilon: f32, margin: f32) -> f32 {
+        const LANES: usize = 16;
+        let chunks = p2.x.len() / LANES;
+        let simd_sum;
+        unsafe {
+            let (x1_n, y1_n, r1_n) = (_mm512_set1_ps(x1), _mm512_set1_ps(y1), _mm512_set1_ps(r1));
+            let e_n = _mm512_set1_ps(epsilon);
+            let e_sq_n = _mm512_set1_ps(epsilon * epsilon);
+            let two_e_n = _mm512_set1_ps(2.0 * epsilon);
+            let margin_n = _mm512_set1_ps(margin);
+
+            let mut acc = _mm512_setzero_ps();
+            for chunk in 0..chunks {
+                let idx = chunk * LANES;
+                let x2 = _mm512_loadu_ps(p2.x.as_ptr().add(idx));
+                let y2 = _mm512_loadu_ps(p2.y.as_ptr().add(idx));
+                let r2 = _mm512_loadu_ps(p2.r.as_ptr().add(idx));
+
+                // calculate pd
+                let dx = _mm512_sub_ps(x1_n, x2);
+                let dy = _mm512_sub_ps(y1_n, y2);
+                let d_sq = _mm512_add_ps(_mm512_mul_ps(dx, dx), _mm512_mul_ps(dy, dy));
+                let dist = _mm512_sqrt_ps(d_sq);
+                let pd = _mm512_sub_ps(_mm512_add_ps(r1_n, r2), dist);
+
+                // pairs out of reach are pruned, as in the scalar version
+                let reach = _mm512_add_ps(_mm512_add_ps(r1_n, r2), margin_n);
+
+                // calculate pd_decay
+                let decay_values = _mm512_div_ps(e_sq_n, _mm512_sub_ps(two_e_n, pd));
+                let pd_mask = _mm512_cmp_ps_mask::<_CMP_GE_OQ>(pd, e_n);
+                let pd_decay = _mm512_mask_blend_ps(pd_mask, decay_values, pd);
+
+                let reach_mask = _mm512_cmp_ps_mask::<_CMP_LE_OQ>(d_sq, _mm512_mul_ps(reach, reach));
+                let contrib = _mm512_mul_ps(pd_decay, _mm512_min_ps(r1_n, r2));
+                acc = _mm512_mask_add_ps(acc, reach_mask, acc, contrib);
+            }
+            simd_sum = _mm512_reduce_add_ps(acc);
+        }
+        simd_sum + pole_overlap_scalar(x1, y1, r1, p2, epsilon, margin, chunks * LANES)
+    }
 }
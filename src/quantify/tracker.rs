@@ -1,27 +1,32 @@
 use jagua_rs::collision_detection::hazards::collector::{BasicHazardCollector, HazardCollector};
 use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::entities::{Layout, PItemKey};
-use crate::consts::{GLS_WEIGHT_DECAY, GLS_WEIGHT_MAX_INC_RATIO, GLS_WEIGHT_MIN_INC_RATIO};
-use crate::quantify::pair_matrix::PairMatrix;
-use crate::quantify::{quantify_collision_poly_container, quantify_collision_poly_poly};
+use crate::quantify::pair_map::PairMap;
+use crate::quantify::weights::GLSWeightConfig;
+use crate::quantify::{quantify_collision_poly_container, OverlapQuantifier};
 use crate::util::assertions::tracker_matches_layout;
+use itertools::Itertools;
 use ordered_float::Float;
 use slotmap::SecondaryMap;
 
 /// Tracker of both collisions between pair of items and collisions with the container.
 /// It also stores the weights for every pair of hazards and is used as a cache for collisions.
+/// Pairs are stored sparsely: only pairs which collide or carry a non-default weight take up memory.
 #[derive(Debug, Clone)]
 pub struct CollisionTracker {
     pub size: usize,
     pub pk_idx_map: SecondaryMap<PItemKey, usize>,
-    pub pair_collisions: PairMatrix,
+    /// Inverse of `pk_idx_map`
+    pub idx_pk_map: Vec<PItemKey>,
+    pub pair_collisions: PairMap,
     pub container_collisions: Vec<CTEntry>,
+    pub quantifier: OverlapQuantifier,
 }
 
 pub type CTSnapshot = CollisionTracker;
 
 impl CollisionTracker {
-    pub fn new(l: &Layout) -> Self {
+    pub fn new(l: &Layout, quantifier: OverlapQuantifier) -> Self {
         let size = l.placed_items.len();
 
         // Create the tracker
@@ -30,8 +35,10 @@ impl CollisionTracker {
             pk_idx_map: l.placed_items.keys().enumerate()
                 .map(|(i, pk)| (pk, i))
                 .collect(),
-            pair_collisions: PairMatrix::new(size),
-            container_collisions: vec![CTEntry { weight: 1.0, loss: 0.0 }; size],
+            idx_pk_map: l.placed_items.keys().collect(),
+            pair_collisions: PairMap::new(size),
+            container_collisions: vec![CTEntry::DEFAULT; size],
+            quantifier,
         };
 
         // Recompute the loss for all items
@@ -50,9 +57,7 @@ impl CollisionTracker {
         let shape = &pi.shape;
 
         // Reset all current loss values for the item
-        for i in 0..self.size {
-            self.pair_collisions[(idx, i)].loss = 0.0;
-        }
+        self.pair_collisions.clear_losses(idx);
         self.container_collisions[idx].loss = 0.0;
 
         // Compute which hazards are currently colliding with the item
@@ -68,9 +73,9 @@ impl CollisionTracker {
                     let shape_other = &l.placed_items[*other_pk].shape;
                     let idx_other = self.pk_idx_map[*other_pk];
 
-                    let loss = quantify_collision_poly_poly(shape, shape_other);
+                    let loss = self.quantifier.quantify_poly_poly(shape, shape_other);
                     assert!(loss > 0.0, "loss for a collision should be > 0.0");
-                    self.pair_collisions[(idx, idx_other)].loss = loss;
+                    self.pair_collisions.set_loss(idx, idx_other, loss);
                 }
                 HazardEntity::Exterior => {
                     let loss = quantify_collision_poly_container(shape, l.container.outer_cd.bbox);
@@ -85,9 +90,13 @@ impl CollisionTracker {
     pub fn restore_but_keep_weights(&mut self, cts: &CTSnapshot, layout: &Layout) {
         //Copy the loss and keys, but keep the weights
         self.pk_idx_map = cts.pk_idx_map.clone();
-        self.pair_collisions.data.iter_mut()
-            .zip(cts.pair_collisions.data.iter())
-            .for_each(|(a, b)| a.loss = b.loss);
+        self.idx_pk_map = cts.idx_pk_map.clone();
+        for idx in 0..self.size {
+            self.pair_collisions.clear_losses(idx);
+        }
+        for (idx1, idx2, e) in cts.pair_collisions.iter().filter(|(_, _, e)| e.loss > 0.0) {
+            self.pair_collisions.set_loss(idx1, idx2, e.loss);
+        }
         self.container_collisions.iter_mut()
             .zip(cts.container_collisions.iter())
             .for_each(|(a, b)| a.loss = b.loss);
@@ -99,34 +108,89 @@ impl CollisionTracker {
     }
 
     pub fn register_item_move(&mut self, l: &Layout, old_pk: PItemKey, new_pk: PItemKey) {
-        //swap the keys in the pk_idx_map
-        let idx = self.pk_idx_map.remove(old_pk).unwrap();
-        self.pk_idx_map.insert(new_pk, idx);
+        self.remap_key(old_pk, new_pk);
 
         self.recompute_loss_for_item(new_pk, l);
 
         debug_assert!(tracker_matches_layout(self, l));
     }
 
-    pub fn increment_weights(&mut self) {
-        let max_loss = self.pair_collisions.data.iter()
-            .chain(self.container_collisions.iter())
+    /// Transfers all state associated with `old_pk` to `new_pk`, without recomputing any loss
+    pub fn remap_key(&mut self, old_pk: PItemKey, new_pk: PItemKey) {
+        let idx = self.pk_idx_map.remove(old_pk).unwrap();
+        self.pk_idx_map.insert(new_pk, idx);
+        self.idx_pk_map[idx] = new_pk;
+    }
+
+    /// Copies the loss and weights of the items at `idxs` from another tracker with the same indices.
+    /// Entries between two items which are not in `idxs` are assumed to be equal in both trackers already.
+    pub fn copy_rows_from(&mut self, other: &CollisionTracker, idxs: &[usize]) {
+        debug_assert!(self.size == other.size);
+        for &idx in idxs {
+            self.container_collisions[idx] = other.container_collisions[idx];
+            self.pair_collisions.copy_row_from(&other.pair_collisions, idx);
+        }
+    }
+
+    pub fn increment_weights(&mut self, config: &GLSWeightConfig) {
+        let max_loss = self.pair_collisions.iter().map(|(_, _, e)| e)
+            .chain(self.container_collisions.iter().copied())
             .map(|e| e.loss)
             .fold(0.0, |a, b| a.max(b));
 
-        for e in self.pair_collisions.data.iter_mut()
-            .chain(self.container_collisions.iter_mut()) {
-            let multiplier = match e.loss == 0.0 {
-                true => GLS_WEIGHT_DECAY, // no collision
-                false => GLS_WEIGHT_MIN_INC_RATIO + (GLS_WEIGHT_MAX_INC_RATIO - GLS_WEIGHT_MIN_INC_RATIO) * (e.loss / max_loss),
-            };
-            e.weight = (e.weight * multiplier).max(1.0);
+        let max_weight = config.max_weight.unwrap_or(f32::INFINITY);
+
+        //pairs which are not stored have the default weight and no loss, which is a fixed point of the rule
+        self.pair_collisions.update_weights(|e| config.pair_rule.apply(e.weight, e.loss, max_loss).min(max_weight));
+        for e in self.container_collisions.iter_mut() {
+            e.weight = config.container_rule.apply(e.weight, e.loss, max_loss).min(max_weight);
         }
     }
 
+    /// Copies the weights from another tracker for all items present in both (matched by key),
+    /// decayed towards 1.0 by `decay`. Weights of other items are left untouched.
+    pub fn inherit_weights(&mut self, other: &CollisionTracker, decay: f32) {
+        let decayed = |w: f32| 1.0 + (w - 1.0) * decay;
+
+        //maps the indices of the other tracker to the indices of this one
+        let mut o_idx_map = vec![None; other.size];
+        let mut shared = vec![false; self.size];
+        for (pk, &idx) in self.pk_idx_map.iter() {
+            if let Some(&o_idx) = other.pk_idx_map.get(pk) {
+                o_idx_map[o_idx] = Some(idx);
+                shared[idx] = true;
+            }
+        }
+
+        for o_idx in 0..other.size {
+            if let Some(idx) = o_idx_map[o_idx] {
+                self.container_collisions[idx].weight = decayed(other.container_collisions[o_idx].weight);
+            }
+        }
+
+        //weights of pairs between shared items which are not stored in the other tracker are at the default
+        let stale_pairs = self.pair_collisions.iter()
+            .filter(|(idx1, idx2, e)| e.weight != 1.0 && shared[*idx1] && shared[*idx2])
+            .collect_vec();
+        for (idx1, idx2, _) in stale_pairs {
+            self.pair_collisions.set_weight(idx1, idx2, 1.0);
+        }
+        for (o_idx1, o_idx2, e) in other.pair_collisions.iter() {
+            if let (Some(idx1), Some(idx2)) = (o_idx_map[o_idx1], o_idx_map[o_idx2]) {
+                self.pair_collisions.set_weight(idx1, idx2, decayed(e.weight));
+            }
+        }
+    }
+
+    pub fn reset_weights(&mut self) {
+        self.pair_collisions.update_weights(|_| 1.0);
+        self.container_collisions.iter_mut()
+            .for_each(|e| e.weight = 1.0);
+    }
+
     pub fn get_pair_weight(&self, pk1: PItemKey, pk2: PItemKey) -> f32 {
         let (idx1, idx2) = (self.pk_idx_map[pk1], self.pk_idx_map[pk2]);
-        self.pair_collisions[(idx1, idx2)].weight
+        self.pair_collisions.get(idx1, idx2).weight
     }
 
     pub fn get_container_weight(&self, pk: PItemKey) -> f32 {
@@ -136,7 +200,7 @@ impl CollisionTracker {
 
     pub fn get_pair_loss(&self, pk1: PItemKey, pk2: PItemKey) -> f32 {
         let (idx1, idx2) = (self.pk_idx_map[pk1], self.pk_idx_map[pk2]);
-        self.pair_collisions[(idx1, idx2)].loss
+        self.pair_collisions.get(idx1, idx2).loss
     }
 
     pub fn get_container_loss(&self, pk: PItemKey) -> f32 {
@@ -147,28 +211,27 @@ impl CollisionTracker {
     pub fn get_loss(&self, pk: PItemKey) -> f32 {
         let idx = self.pk_idx_map[pk];
 
-        let pair_loss = (0..self.size)
-            .map(|i| self.pair_collisions[(idx, i)].loss)
-            .sum::<f32>();
-
-        self.container_collisions[idx].loss + pair_loss
+        self.container_collisions[idx].loss + self.pair_collisions.sums(idx).loss
     }
 
     pub fn get_weighted_loss(&self, pk: PItemKey) -> f32 {
         let idx = self.pk_idx_map[pk];
 
-        let w_pair_loss = (0..self.size)
-            .map(|i| self.pair_collisions[(idx, i)].weighted_loss())
-            .sum::<f32>();
+        self.container_collisions[idx].weighted_loss() + self.pair_collisions.sums(idx).weighted_loss
+    }
 
-        self.container_collisions[idx].weighted_loss() + w_pair_loss
+    /// Sum of all weights associated with an item, in excess of the default weight (1.0)
+    pub fn get_excess_weight(&self, pk: PItemKey) -> f32 {
+        let idx = self.pk_idx_map[pk];
+
+        (self.container_collisions[idx].weight - 1.0) + self.pair_collisions.sums(idx).excess_weight
     }
 
     pub fn get_total_loss(&self) -> f32 {
         let cont_o = self.container_collisions.iter().map(|e| e.loss).sum::<f32>();
 
-        let pair_o = self.pair_collisions.data.iter()
-            .map(|e| e.loss)
+        let pair_o = self.pair_collisions.iter()
+            .map(|(_, _, e)| e.loss)
             .sum::<f32>();
 
         cont_o + pair_o
@@ -179,8 +242,8 @@ impl CollisionTracker {
             .map(|e| e.weighted_loss())
             .sum::<f32>();
 
-        let pair_w_o = self.pair_collisions.data.iter()
-            .map(|e| e.weighted_loss())
+        let pair_w_o = self.pair_collisions.iter()
+            .map(|(_, _, e)| e.weighted_loss())
             .sum::<f32>();
 
         cont_w_o + pair_w_o
@@ -194,6 +257,12 @@ pub struct CTEntry {
 }
 
 impl CTEntry {
+    pub const DEFAULT: CTEntry = CTEntry { loss: 0.0, weight: 1.0 };
+
+    pub fn is_default(&self) -> bool {
+        self.loss == 0.0 && self.weight == 1.0
+    }
+
     pub fn weighted_loss(&self) -> f32 {
         self.weight * self.loss
     }
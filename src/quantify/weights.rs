@@ -0,0 +1,51 @@
+/// Rule to update the guided local search (GLS) weight of a single entry of the [`CollisionTracker`](crate::quantify::tracker::CollisionTracker).
+#[derive(Debug, Clone, Copy)]
+pub enum WeightUpdateRule {
+    /// Weights of colliding entries are multiplied by a ratio in `[min_inc_ratio, max_inc_ratio]`,
+    /// depending on their loss relative to the maximum loss. Other weights decay by a fixed ratio.
+    Multiplicative {
+        min_inc_ratio: f32,
+        max_inc_ratio: f32,
+        decay: f32,
+    },
+    /// Weights of colliding entries are incremented by a value in `[min_inc, max_inc]`,
+    /// depending on their loss relative to the maximum loss. Other weights decay by a fixed ratio.
+    Additive {
+        min_inc: f32,
+        max_inc: f32,
+        decay: f32,
+    },
+}
+
+impl WeightUpdateRule {
+    /// Returns the updated weight of an entry, given its current loss and the maximum loss in the tracker
+    pub fn apply(&self, weight: f32, loss: f32, max_loss: f32) -> f32 {
+        let updated = match (*self, loss == 0.0) {
+            (WeightUpdateRule::Multiplicative { decay, .. }, true) |
+            (WeightUpdateRule::Additive { decay, .. }, true) => weight * decay, // no collision
+            (WeightUpdateRule::Multiplicative { min_inc_ratio, max_inc_ratio, .. }, false) => {
+                weight * (min_inc_ratio + (max_inc_ratio - min_inc_ratio) * (loss / max_loss))
+            }
+            (WeightUpdateRule::Additive { min_inc, max_inc, .. }, false) => {
+                weight + min_inc + (max_inc - min_inc) * (loss / max_loss)
+            }
+        };
+        updated.max(1.0)
+    }
+}
+
+/// Configuration of the GLS weight schedule used during separation.
+#[derive(Debug, Clone, Copy)]
+pub struct GLSWeightConfig {
+    /// Update rule for the weights of collisions between pairs of items
+    pub pair_rule: WeightUpdateRule,
+    /// Update rule for the weights of collisions between items and the container
+    pub container_rule: WeightUpdateRule,
+    /// Upper bound for all weights, uncapped if `None`
+    pub max_weight: Option<f32>,
+    /// Resets all weights to 1.0 after this many consecutive strikes, never if `None`
+    pub reset_after_strikes: Option<usize>,
+    /// Carries the weights over when the strip width changes, decayed towards 1.0 by this ratio
+    /// (1.0 keeps them as is). All weights start again from 1.0 if `None`
+    pub carry_over_decay: Option<f32>,
+}
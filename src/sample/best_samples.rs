@@ -73,6 +73,13 @@ impl BestSamples {
         }
     }
 
+    /// Reports all samples of `other`, in order
+    pub fn merge(&mut self, other: &BestSamples) {
+        for (dt, eval) in other.samples.iter() {
+            self.report(*dt, *eval);
+        }
+    }
+
     pub fn best(&self) -> Option<(DTransformation, SampleEval)> {
         self.samples.first().cloned()
     }
@@ -45,7 +45,8 @@ pub fn refine_coord_desc(
     // From the CD state, ask for candidate positions to evaluate. If none provided, stop.
     while let Some(c) = cd.ask() {
         // Evaluate the candidates using the evaluator.
-        let c_eval = c.map(|c| evaluator.eval(c, Some(cd.eval)));
+        let mut c_eval = [SampleEval::Invalid; 2];
+        evaluator.eval_many(&c, Some(cd.eval), &mut c_eval);
         
         let best = c.into_iter().zip(c_eval)
             .min_by_key(|(_, eval)| *eval)
@@ -0,0 +1,220 @@
+use itertools::Itertools;
+use jagua_rs::entities::{Item, Layout, PItemKey, PlacedItem};
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::{CollidesWith, TransformableFrom};
+use jagua_rs::geometry::primitives::{Point, Rect};
+use jagua_rs::geometry::Transformation;
+use rand::prelude::IndexedRandom;
+use rand::Rng;
+use slotmap::SecondaryMap;
+
+/// Coarse rasterization of a layout, used by the [`FreeSpaceSampler`].
+/// A cell is occupied if its center is inside any of the placed items.
+/// The raster is kept up to date incrementally: only items which were moved since the last [`FreeSpaceRaster::sync`] are (un)rasterized.
+#[derive(Clone, Debug, Default)]
+pub struct FreeSpaceRaster {
+    /// Bounding box of the container and resolution the raster was built for
+    dims: Option<(Rect, usize)>,
+    cell_size: f32,
+    n_rows: usize,
+    n_cols: usize,
+    /// Number of placed items covering the center of each cell
+    coverage: Vec<u32>,
+    /// Placement and covered cells of every rasterized item
+    items: SecondaryMap<PItemKey, RasterizedItem>,
+}
+
+#[derive(Clone, Debug)]
+struct RasterizedItem {
+    /// (item id, rotation, translation) of the placement when it was rasterized
+    placement: (usize, f32, (f32, f32)),
+    cells: Vec<usize>,
+}
+
+impl FreeSpaceRaster {
+    /// Brings the raster up to date with the layout.
+    /// Items which were removed or moved since the last sync are erased, new ones are rasterized.
+    /// The raster is rebuilt from scratch if the container or the resolution changed.
+    pub fn sync(&mut self, l: &Layout, resolution: usize) {
+        let c_bbox = l.container.outer_cd.bbox;
+        let same_dims = self.dims.is_some_and(|(bbox, res)| {
+            res == resolution && (bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max) == (c_bbox.x_min, c_bbox.y_min, c_bbox.x_max, c_bbox.y_max)
+        });
+        if !same_dims {
+            let cell_size = c_bbox.height() / resolution as f32;
+            let n_cols = (c_bbox.width() / cell_size).ceil() as usize;
+            *self = Self {
+                dims: Some((c_bbox, resolution)),
+                cell_size,
+                n_rows: resolution,
+                n_cols,
+                coverage: vec![0; resolution * n_cols],
+                items: SecondaryMap::new(),
+            };
+        }
+
+        //erase all items which are no longer placed as they were rasterized
+        let stale = self.items.iter()
+            .filter(|(pk, ri)| l.placed_items.get(*pk).is_none_or(|pi| placement_of(pi) != ri.placement))
+            .map(|(pk, _)| pk)
+            .collect_vec();
+        for pk in stale {
+            for idx in self.items.remove(pk).unwrap().cells {
+                self.coverage[idx] -= 1;
+            }
+        }
+
+        //rasterize all items which are new
+        for (pk, pi) in l.placed_items.iter() {
+            if !self.items.contains_key(pk) {
+                let cells = self.rasterize(pi);
+                for &idx in cells.iter() {
+                    self.coverage[idx] += 1;
+                }
+                self.items.insert(pk, RasterizedItem { placement: placement_of(pi), cells });
+            }
+        }
+    }
+
+    /// Indices of the cells whose center is inside the placed item
+    fn rasterize(&self, pi: &PlacedItem) -> Vec<usize> {
+        let (c_bbox, _) = self.dims.expect("raster is not initialized");
+        let bbox = pi.shape.bbox;
+        let col_range = cell_index(bbox.x_min - c_bbox.x_min, self.cell_size, self.n_cols)..=cell_index(bbox.x_max - c_bbox.x_min, self.cell_size, self.n_cols);
+        let row_range = cell_index(bbox.y_min - c_bbox.y_min, self.cell_size, self.n_rows)..=cell_index(bbox.y_max - c_bbox.y_min, self.cell_size, self.n_rows);
+        row_range.cartesian_product(col_range)
+            .filter(|&(row, col)| {
+                let center = self.cell_center(row, col);
+                pi.shape.collides_with(&center)
+            })
+            .map(|(row, col)| row * self.n_cols + col)
+            .collect_vec()
+    }
+
+    fn cell_center(&self, row: usize, col: usize) -> Point {
+        let (c_bbox, _) = self.dims.expect("raster is not initialized");
+        Point(
+            c_bbox.x_min + (col as f32 + 0.5) * self.cell_size,
+            c_bbox.y_min + (row as f32 + 0.5) * self.cell_size,
+        )
+    }
+}
+
+fn placement_of(pi: &PlacedItem) -> (usize, f32, (f32, f32)) {
+    (pi.item_id, pi.d_transf.rotation(), pi.d_transf.translation())
+}
+
+/// A sampler that biases samples towards the unoccupied regions of a layout.
+/// Samples are drawn from the free squares of a [`FreeSpaceRaster`]
+/// large enough to (potentially) accommodate the item.
+#[derive(Clone, Debug)]
+pub struct FreeSpaceSampler {
+    /// Free squares of the grid: (x_min, y_min, side)
+    pub gaps: Vec<(f32, f32, f32)>,
+    rot_entries: Vec<FreeSpaceRotEntry>,
+}
+
+#[derive(Clone, Debug)]
+struct FreeSpaceRotEntry {
+    r: f32,
+    /// Offset from the reference point of the item to the center of its rotated bounding box
+    center_offset: (f32, f32),
+    /// Range of valid translations in which the item resides inside the container
+    x_range: (f32, f32),
+    y_range: (f32, f32),
+}
+
+impl FreeSpaceSampler {
+    /// Creates a sampler from a raster which is in sync with the layout, see [`FreeSpaceRaster::sync`].
+    /// The item itself (`ref_pk`) is not considered as occupying space.
+    pub fn new(raster: &FreeSpaceRaster, l: &Layout, item: &Item, ref_pk: Option<PItemKey>) -> Option<Self> {
+        let (c_bbox, _) = raster.dims?;
+        let (cell_size, n_rows, n_cols) = (raster.cell_size, raster.n_rows, raster.n_cols);
+
+        let mut occupied = raster.coverage.iter().map(|&c| c > 0).collect_vec();
+        if let Some(ri) = ref_pk.and_then(|pk| raster.items.get(pk)) {
+            for &idx in ri.cells.iter() {
+                occupied[idx] = raster.coverage[idx] > 1;
+            }
+        }
+
+        // for every cell, determine the largest free square with its top-right corner in that cell
+        let mut free_square = vec![0usize; n_rows * n_cols];
+        for row in 0..n_rows {
+            for col in 0..n_cols {
+                let idx = row * n_cols + col;
+                if !occupied[idx] {
+                    free_square[idx] = match row == 0 || col == 0 {
+                        true => 1,
+                        false => 1 + free_square[idx - n_cols]
+                            .min(free_square[idx - 1])
+                            .min(free_square[idx - n_cols - 1]),
+                    };
+                }
+            }
+        }
+
+        // only gaps at least as large as the smallest dimension of the item are interesting
+        let item_min_dim = f32::min(item.shape_cd.bbox.width(), item.shape_cd.bbox.height());
+        let min_side = usize::max(1, (item_min_dim / cell_size).ceil() as usize);
+
+        let gaps = (0..n_rows).cartesian_product(0..n_cols)
+            .filter(|(row, col)| free_square[row * n_cols + col] >= min_side)
+            .map(|(row, col)| {
+                let side = free_square[row * n_cols + col];
+                let x_min = c_bbox.x_min + (col + 1 - side) as f32 * cell_size;
+                let y_min = c_bbox.y_min + (row + 1 - side) as f32 * cell_size;
+                (x_min, y_min, side as f32 * cell_size)
+            })
+            .collect_vec();
+
+        let rotations = match &item.allowed_rotation {
+            RotationRange::None => vec![0.0],
+            RotationRange::Discrete(r) => r.clone(),
+            RotationRange::Continuous => match ref_pk {
+                Some(pk) => vec![l.placed_items[pk].d_transf.rotation()],
+                None => vec![0.0],
+            },
+        };
+
+        let mut shape_buffer = item.shape_cd.as_ref().clone();
+        let rot_entries = rotations.into_iter()
+            .map(|r| {
+                let r_bbox = shape_buffer.transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r)).bbox;
+                let center = r_bbox.centroid();
+                FreeSpaceRotEntry {
+                    r,
+                    center_offset: (center.0, center.1),
+                    x_range: (c_bbox.x_min - r_bbox.x_min, c_bbox.x_max - r_bbox.x_max),
+                    y_range: (c_bbox.y_min - r_bbox.y_min, c_bbox.y_max - r_bbox.y_max),
+                }
+            })
+            .filter(|e| e.x_range.0 < e.x_range.1 && e.y_range.0 < e.y_range.1)
+            .collect_vec();
+
+        match gaps.is_empty() || rot_entries.is_empty() {
+            true => None,
+            false => Some(Self { gaps, rot_entries }),
+        }
+    }
+
+    /// Samples a transformation which centers the item in a random gap of the layout.
+    pub fn sample(&self, rng: &mut impl Rng) -> DTransformation {
+        let (x_min, y_min, side) = *self.gaps.choose(rng).unwrap();
+        let r_entry = self.rot_entries.choose(rng).unwrap();
+
+        let x = x_min + rng.random_range(0.0..side) - r_entry.center_offset.0;
+        let y = y_min + rng.random_range(0.0..side) - r_entry.center_offset.1;
+
+        // make sure the item resides inside the container
+        let x = x.clamp(r_entry.x_range.0, r_entry.x_range.1);
+        let y = y.clamp(r_entry.y_range.0, r_entry.y_range.1);
+
+        DTransformation::new(r_entry.r, (x, y))
+    }
+}
+
+fn cell_index(v: f32, cell_size: f32, n_cells: usize) -> usize {
+    ((v / cell_size).floor().max(0.0) as usize).min(n_cells - 1)
+}
@@ -1,4 +1,7 @@
 mod best_samples;
-mod coord_descent;
+pub mod coord_descent;
+pub mod free_space_sampler;
+pub mod nfp_sampler;
+pub mod refine;
 pub mod search;
 pub mod uniform_sampler;
\ No newline at end of file
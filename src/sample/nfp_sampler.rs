@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use itertools::Itertools;
+use jagua_rs::entities::{Item, Layout, PItemKey, PlacedItem};
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::{Point, Rect, SPolygon};
+use jagua_rs::geometry::Transformation;
+use ordered_float::OrderedFloat;
+use rand::prelude::IndexedRandom;
+use rand::Rng;
+use rand::seq::IteratorRandom;
+
+/// Maximum number of neighbouring items considered by a single [`NFPSampler`]
+const MAX_NFP_NEIGHBOURS: usize = 16;
+
+/// Maximum number of no-fit polygons kept in the cache before it is flushed
+const NFP_CACHE_CAPACITY: usize = 100_000;
+
+/// Maximum number of attempts to generate a candidate inside the container
+const MAX_NFP_SAMPLE_ATTEMPTS: usize = 8;
+
+/// Cache of no-fit polygons between the convex hulls of pairs of (rotated) items.
+#[derive(Debug, Clone, Default)]
+pub struct NFPCache {
+    nfps: HashMap<NFPKey, Arc<Vec<Point>>>,
+}
+
+/// (moving item id, moving rotation, fixed item id, fixed rotation), rotations as raw bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct NFPKey(usize, u32, usize, u32);
+
+impl NFPCache {
+    /// Returns the no-fit polygon of `moving` (rotated by `r_moving`) around the placed item `fixed`,
+    /// relative to the translation of the fixed item.
+    /// Its boundary contains all translations for which the convex hulls of both items touch.
+    pub fn get(&mut self, moving: &Item, r_moving: f32, fixed: &PlacedItem) -> Arc<Vec<Point>> {
+        let r_fixed = fixed.d_transf.rotation();
+        let key = NFPKey(moving.id, r_moving.to_bits(), fixed.item_id, r_fixed.to_bits());
+        if self.nfps.len() >= NFP_CACHE_CAPACITY && !self.nfps.contains_key(&key) {
+            self.nfps.clear();
+        }
+        self.nfps.entry(key)
+            .or_insert_with(|| {
+                let hull_moving = rotated_convex_hull(&moving.shape_cd, r_moving);
+                // hull of the fixed item, without its translation
+                let (tx, ty) = fixed.d_transf.translation();
+                let hull_fixed = convex_hull(
+                    fixed.shape.vertices.iter().map(|p| Point(p.0 - tx, p.1 - ty)).collect_vec()
+                );
+                Arc::new(convex_nfp(&hull_fixed, &hull_moving))
+            })
+            .clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nfps.len()
+    }
+}
+
+/// A sampler that proposes transformations for which the item touches one of its neighbours,
+/// by sampling vertices and edges of the no-fit polygons (of their convex hulls).
+#[derive(Debug, Clone)]
+pub struct NFPSampler {
+    entries: Vec<NFPEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct NFPEntry {
+    /// Rotation of the moving item
+    r: f32,
+    /// Translation of the fixed neighbour
+    offset: (f32, f32),
+    nfp: Arc<Vec<Point>>,
+    /// Range of valid translations in which the item resides inside the container
+    x_range: (f32, f32),
+    y_range: (f32, f32),
+}
+
+impl NFPSampler {
+    /// Creates a sampler for the neighbours whose bounding box intersects `focus_bbox`.
+    /// If the item is already placed (`ref_pk`), it is not considered its own neighbour.
+    pub fn new(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, focus_bbox: Rect, cache: &mut NFPCache, rng: &mut impl Rng) -> Option<Self> {
+        let rotations = match (&item.allowed_rotation, ref_pk) {
+            (RotationRange::None, _) => vec![0.0],
+            (RotationRange::Discrete(r), _) => r.clone(),
+            // for continuous rotation, only consider the current rotation of the item
+            (RotationRange::Continuous, Some(pk)) => vec![l.placed_items[pk].d_transf.rotation()],
+            (RotationRange::Continuous, None) => vec![0.0],
+        };
+
+        let container_bbox = l.container.outer_cd.bbox;
+        let mut shape_buffer = item.shape_cd.as_ref().clone();
+        let ranges = rotations.iter()
+            .map(|&r| {
+                let r_bbox = shape_buffer.transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r)).bbox;
+                let x_range = (container_bbox.x_min - r_bbox.x_min, container_bbox.x_max - r_bbox.x_max);
+                let y_range = (container_bbox.y_min - r_bbox.y_min, container_bbox.y_max - r_bbox.y_max);
+                (r, x_range, y_range)
+            })
+            .filter(|(_, x_range, y_range)| x_range.0 < x_range.1 && y_range.0 < y_range.1)
+            .collect_vec();
+
+        let neighbours = l.placed_items.iter()
+            .filter(|(pk, _)| Some(*pk) != ref_pk)
+            .filter(|(_, pi)| Rect::intersection(pi.shape.bbox, focus_bbox).is_some())
+            .choose_multiple(rng, MAX_NFP_NEIGHBOURS);
+
+        let entries = neighbours.into_iter()
+            .flat_map(|(_, pi)| {
+                let offset = pi.d_transf.translation();
+                ranges.iter()
+                    .map(|&(r, x_range, y_range)| NFPEntry {
+                        r,
+                        offset,
+                        nfp: cache.get(item, r, pi),
+                        x_range,
+                        y_range,
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        match entries.is_empty() {
+            true => None,
+            false => Some(Self { entries }),
+        }
+    }
+
+    /// Samples a vertex or a point on an edge of a random no-fit polygon.
+    /// Returns `None` if no candidate inside the container was found.
+    pub fn sample(&self, rng: &mut impl Rng) -> Option<DTransformation> {
+        for _ in 0..MAX_NFP_SAMPLE_ATTEMPTS {
+            let entry = self.entries.choose(rng).unwrap();
+            let n = entry.nfp.len();
+            let i = rng.random_range(0..n);
+            let (p1, p2) = (entry.nfp[i], entry.nfp[(i + 1) % n]);
+            // vertices of the no-fit polygon are the most interesting candidates (touching on two sides)
+            let t = match rng.random_bool(0.5) {
+                true => 0.0,
+                false => rng.random_range(0.0..1.0),
+            };
+            let x = entry.offset.0 + p1.0 + t * (p2.0 - p1.0);
+            let y = entry.offset.1 + p1.1 + t * (p2.1 - p1.1);
+
+            let in_container = (entry.x_range.0..=entry.x_range.1).contains(&x)
+                && (entry.y_range.0..=entry.y_range.1).contains(&y);
+            if in_container {
+                return Some(DTransformation::new(entry.r, (x, y)));
+            }
+        }
+        None
+    }
+}
+
+/// Convex hull of a shape, rotated around the origin.
+pub fn rotated_convex_hull(shape: &SPolygon, r: f32) -> Vec<Point> {
+    let (sin, cos) = r.sin_cos();
+    let rotated = shape.vertices.iter()
+        .map(|p| Point(cos * p.0 - sin * p.1, sin * p.0 + cos * p.1))
+        .collect_vec();
+    convex_hull(rotated)
+}
+
+/// Convex hull of a set of points (counterclockwise), using Andrew's monotone chain algorithm.
+pub fn convex_hull(mut points: Vec<Point>) -> Vec<Point> {
+    points.sort_by_key(|p| (OrderedFloat(p.0), OrderedFloat(p.1)));
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross = |o: Point, a: Point, b: Point| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+
+    let mut lower: Vec<Point> = vec![];
+    for &p in points.iter() {
+        while lower.len() >= 2 && cross(lower[lower.len() - 2], lower[lower.len() - 1], p) <= 0.0 {
+            lower.pop();
+        }
+        lower.push(p);
+    }
+    let mut upper: Vec<Point> = vec![];
+    for &p in points.iter().rev() {
+        while upper.len() >= 2 && cross(upper[upper.len() - 2], upper[upper.len() - 1], p) <= 0.0 {
+            upper.pop();
+        }
+        upper.push(p);
+    }
+    // last point of each chain is the first point of the other
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+/// No-fit polygon of two convex polygons: the Minkowski sum of `fixed` and `-moving`.
+pub fn convex_nfp(fixed: &[Point], moving: &[Point]) -> Vec<Point> {
+    let sums = fixed.iter()
+        .cartesian_product(moving.iter())
+        .map(|(f, m)| Point(f.0 - m.0, f.1 - m.1))
+        .collect_vec();
+    convex_hull(sums)
+}
@@ -0,0 +1,298 @@
+use crate::consts::{CD_STEP_FAIL, CD_STEP_SUCCESS};
+use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::sample::coord_descent::{refine_coord_desc, CDConfig};
+use itertools::Itertools;
+use jagua_rs::geometry::DTransformation;
+use log::trace;
+use rand::Rng;
+
+/// Maximum number of iterations of the Nelder-Mead refiner
+const NM_MAX_ITER: usize = 100;
+
+/// Relative step used to approximate the gradient with finite differences
+const GRAD_FD_RATIO: f32 = 0.5;
+
+/// Strategies to refine an initial 'sample' (transformation and evaluation) into a local minimum.
+/// All of them share the step sizes and limits defined in [`CDConfig`].
+pub trait Refiner {
+    fn refine(
+        &self,
+        init: (DTransformation, SampleEval),
+        evaluator: &mut impl SampleEvaluator,
+        rng: &mut impl Rng,
+    ) -> (DTransformation, SampleEval);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefinerType {
+    /// See [`CoordDescentRefiner`]
+    CoordinateDescent,
+    /// See [`PatternSearchRefiner`]
+    PatternSearch,
+    /// See [`NelderMeadRefiner`]
+    NelderMead,
+    /// See [`GradientRefiner`]
+    Gradient,
+}
+
+/// Refines a sample with the refiner of the given type.
+pub fn refine(
+    refiner: RefinerType,
+    init: (DTransformation, SampleEval),
+    evaluator: &mut impl SampleEvaluator,
+    cd_config: CDConfig,
+    rng: &mut impl Rng,
+) -> (DTransformation, SampleEval) {
+    match refiner {
+        RefinerType::CoordinateDescent => CoordDescentRefiner(cd_config).refine(init, evaluator, rng),
+        RefinerType::PatternSearch => PatternSearchRefiner(cd_config).refine(init, evaluator, rng),
+        RefinerType::NelderMead => NelderMeadRefiner(cd_config).refine(init, evaluator, rng),
+        RefinerType::Gradient => GradientRefiner(cd_config).refine(init, evaluator, rng),
+    }
+}
+
+/// Coordinate descent along a random axis, see [`refine_coord_desc`]
+#[derive(Debug, Clone, Copy)]
+pub struct CoordDescentRefiner(pub CDConfig);
+
+impl Refiner for CoordDescentRefiner {
+    fn refine(&self, init: (DTransformation, SampleEval), evaluator: &mut impl SampleEvaluator, rng: &mut impl Rng) -> (DTransformation, SampleEval) {
+        refine_coord_desc(init, evaluator, self.0, rng)
+    }
+}
+
+/// Compass (pattern) search: polls all axes at once and moves to the best improving candidate.
+/// After an improving move, the same move is extrapolated once more (pattern move).
+/// Steps shrink when no axis yields an improvement.
+#[derive(Debug, Clone, Copy)]
+pub struct PatternSearchRefiner(pub CDConfig);
+
+impl Refiner for PatternSearchRefiner {
+    fn refine(&self, (init_dt, init_eval): (DTransformation, SampleEval), evaluator: &mut impl SampleEvaluator, _rng: &mut impl Rng) -> (DTransformation, SampleEval) {
+        let cfg = self.0;
+        let (mut pos, mut eval) = (init_dt, init_eval);
+        let (mut t_step, mut r_step) = (cfg.t_step_init, cfg.r_step_init);
+
+        loop {
+            let t_active = t_step >= cfg.t_step_limit;
+            let r_active = cfg.wiggle && r_step >= cfg.r_step_limit;
+            if !t_active && !r_active {
+                break;
+            }
+
+            let (tx, ty) = pos.translation();
+            let r = pos.rotation();
+            let mut candidates = vec![];
+            if t_active {
+                candidates.extend([(tx + t_step, ty, r), (tx - t_step, ty, r), (tx, ty + t_step, r), (tx, ty - t_step, r)]);
+            }
+            if r_active {
+                candidates.extend([(tx, ty, r + r_step), (tx, ty, r - r_step)]);
+            }
+
+            let candidates = candidates.into_iter()
+                .map(|(x, y, r)| DTransformation::new(r, (x, y)))
+                .collect_vec();
+            let mut evals = vec![SampleEval::Invalid; candidates.len()];
+            evaluator.eval_many(&candidates, Some(eval), &mut evals);
+
+            let best = candidates.into_iter().zip(evals)
+                .min_by_key(|(_, e)| *e)
+                .unwrap();
+
+            if best.1 < eval {
+                // pattern move: try to continue in the same direction
+                let (bx, by) = best.0.translation();
+                let pattern = DTransformation::new(
+                    2.0 * best.0.rotation() - r,
+                    (2.0 * bx - tx, 2.0 * by - ty),
+                );
+                let pattern_eval = evaluator.eval(pattern, Some(best.1));
+                (pos, eval) = match pattern_eval < best.1 {
+                    true => (pattern, pattern_eval),
+                    false => best,
+                };
+                t_step *= CD_STEP_SUCCESS;
+                r_step *= CD_STEP_SUCCESS;
+            } else {
+                t_step *= CD_STEP_FAIL;
+                r_step *= CD_STEP_FAIL;
+            }
+        }
+        trace!("PS: {} -> {}, eval: {:?}", init_dt, pos, eval);
+        (pos, eval)
+    }
+}
+
+/// Nelder-Mead simplex search on (x, y) or (x, y, r) if the wiggle axis is enabled.
+/// Only relies on the ordering of [`SampleEval`]s, not on their absolute values.
+#[derive(Debug, Clone, Copy)]
+pub struct NelderMeadRefiner(pub CDConfig);
+
+impl Refiner for NelderMeadRefiner {
+    fn refine(&self, (init_dt, init_eval): (DTransformation, SampleEval), evaluator: &mut impl SampleEvaluator, _rng: &mut impl Rng) -> (DTransformation, SampleEval) {
+        let cfg = self.0;
+        let dim = if cfg.wiggle { 3 } else { 2 };
+        let init_r = init_dt.rotation();
+        let to_dt = |p: &[f32]| {
+            let r = if dim == 3 { p[2] } else { init_r };
+            DTransformation::new(r, (p[0], p[1]))
+        };
+        let steps = [cfg.t_step_init, cfg.t_step_init, cfg.r_step_init];
+        let limits = [cfg.t_step_limit, cfg.t_step_limit, cfg.r_step_limit];
+
+        // initial simplex: the initial sample and one step along every axis
+        let (tx, ty) = init_dt.translation();
+        let x0 = [tx, ty, init_r][..dim].to_vec();
+        let mut simplex = vec![(x0.clone(), init_eval)];
+        for i in 0..dim {
+            let mut p = x0.clone();
+            p[i] += steps[i];
+            let e = evaluator.eval(to_dt(&p), None);
+            simplex.push((p, e));
+        }
+
+        for _ in 0..NM_MAX_ITER {
+            simplex.sort_by_key(|(_, e)| *e);
+
+            let converged = {
+                let best = &simplex[0].0;
+                simplex.iter().all(|(p, _)| (0..dim).all(|i| (p[i] - best[i]).abs() < limits[i]))
+            };
+            if converged {
+                break;
+            }
+
+            let (worst, worst_eval) = simplex[dim].clone();
+            let centroid = (0..dim)
+                .map(|i| simplex[..dim].iter().map(|(p, _)| p[i]).sum::<f32>() / dim as f32)
+                .collect_vec();
+            // point on the line from the worst vertex through the centroid of the others
+            let along = |t: f32| (0..dim).map(|i| centroid[i] + t * (centroid[i] - worst[i])).collect_vec();
+
+            let xr = along(1.0);
+            let er = evaluator.eval(to_dt(&xr), Some(worst_eval));
+
+            let replacement = if er < simplex[0].1 {
+                // expansion
+                let xe = along(2.0);
+                let ee = evaluator.eval(to_dt(&xe), Some(er));
+                match ee < er {
+                    true => Some((xe, ee)),
+                    false => Some((xr, er)),
+                }
+            } else if er < simplex[dim - 1].1 {
+                Some((xr, er))
+            } else if er < worst_eval {
+                // outside contraction
+                let xc = along(0.5);
+                let ec = evaluator.eval(to_dt(&xc), Some(er));
+                match ec <= er {
+                    true => Some((xc, ec)),
+                    false => None,
+                }
+            } else {
+                // inside contraction
+                let xc = along(-0.5);
+                let ec = evaluator.eval(to_dt(&xc), Some(worst_eval));
+                match ec < worst_eval {
+                    true => Some((xc, ec)),
+                    false => None,
+                }
+            };
+
+            match replacement {
+                Some(v) => simplex[dim] = v,
+                None => {
+                    // shrink all vertices towards the best one
+                    let best = simplex[0].0.clone();
+                    for (p, e) in simplex.iter_mut().skip(1) {
+                        p.iter_mut().zip(best.iter()).for_each(|(pi, bi)| *pi = bi + 0.5 * (*pi - bi));
+                        *e = evaluator.eval(to_dt(&p[..]), None);
+                    }
+                }
+            }
+        }
+
+        let (best, best_eval) = simplex.into_iter().min_by_key(|(_, e)| *e).unwrap();
+        trace!("NM: {} -> {}, eval: {:?}", init_dt, to_dt(&best), best_eval);
+        (to_dt(&best), best_eval)
+    }
+}
+
+/// Descent along the (negative) gradient of the loss, approximated with finite differences.
+/// The step along the gradient grows on success and shrinks on failure, like coordinate descent.
+/// Invalid samples are treated as infinite loss, axes without any finite difference are not moved along.
+#[derive(Debug, Clone, Copy)]
+pub struct GradientRefiner(pub CDConfig);
+
+impl Refiner for GradientRefiner {
+    fn refine(&self, (init_dt, init_eval): (DTransformation, SampleEval), evaluator: &mut impl SampleEvaluator, _rng: &mut impl Rng) -> (DTransformation, SampleEval) {
+        let cfg = self.0;
+        let (mut pos, mut eval) = (init_dt, init_eval);
+        let (mut t_step, mut r_step) = (cfg.t_step_init, cfg.r_step_init);
+
+        while t_step >= cfg.t_step_limit || (cfg.wiggle && r_step >= cfg.r_step_limit) {
+            let (tx, ty) = pos.translation();
+            let r = pos.rotation();
+
+            // central (or one-sided) finite differences
+            let mut fd = |h: f32, offset: fn(f32) -> (f32, f32, f32)| -> f32 {
+                let (dx, dy, dr) = offset(h);
+                let plus = loss_value(evaluator.eval(DTransformation::new(r + dr, (tx + dx, ty + dy)), None));
+                let minus = loss_value(evaluator.eval(DTransformation::new(r - dr, (tx - dx, ty - dy)), None));
+                let center = loss_value(eval);
+                match (plus.is_finite(), minus.is_finite()) {
+                    (true, true) => (plus - minus) / (2.0 * h),
+                    (true, false) => (plus - center) / h,
+                    (false, true) => (center - minus) / h,
+                    (false, false) => 0.0,
+                }
+            };
+            let h_t = t_step * GRAD_FD_RATIO;
+            let g_x = fd(h_t, |h| (h, 0.0, 0.0));
+            let g_y = fd(h_t, |h| (0.0, h, 0.0));
+            let g_r = match cfg.wiggle {
+                true => fd(r_step * GRAD_FD_RATIO, |h| (0.0, 0.0, h)),
+                false => 0.0,
+            };
+
+            let g_t_norm = (g_x * g_x + g_y * g_y).sqrt();
+            if g_t_norm == 0.0 && g_r == 0.0 {
+                // flat neighbourhood, nothing to descend
+                break;
+            }
+
+            let (dx, dy) = match g_t_norm > 0.0 {
+                true => (-g_x / g_t_norm * t_step, -g_y / g_t_norm * t_step),
+                false => (0.0, 0.0),
+            };
+            let dr = match g_r == 0.0 {
+                true => 0.0,
+                false => -g_r.signum() * r_step,
+            };
+
+            let candidate = DTransformation::new(r + dr, (tx + dx, ty + dy));
+            let c_eval = evaluator.eval(candidate, Some(eval));
+
+            if c_eval < eval {
+                (pos, eval) = (candidate, c_eval);
+                t_step *= CD_STEP_SUCCESS;
+                r_step *= CD_STEP_SUCCESS;
+            } else {
+                t_step *= CD_STEP_FAIL;
+                r_step *= CD_STEP_FAIL;
+            }
+        }
+        trace!("GD: {} -> {}, eval: {:?}", init_dt, pos, eval);
+        (pos, eval)
+    }
+}
+
+/// Numerical value of an evaluation, invalid samples have infinite loss
+fn loss_value(eval: SampleEval) -> f32 {
+    match eval {
+        SampleEval::Clear { loss } | SampleEval::Collision { loss } => loss,
+        SampleEval::Invalid => f32::INFINITY,
+    }
+}
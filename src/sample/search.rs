@@ -1,10 +1,14 @@
 use jagua_rs::entities::{Item, Layout, PItemKey};
 use jagua_rs::geometry::DTransformation;
 use jagua_rs::geometry::geo_enums::RotationRange;
-use crate::consts::{SND_REFINE_CD_TL_RATIOS, PRE_REFINE_CD_TL_RATIOS, UNIQUE_SAMPLE_THRESHOLD, PRE_REFINE_CD_R_STEPS, SND_REFINE_CD_R_STEPS};
+use jagua_rs::geometry::primitives::Rect;
+use crate::consts::{SND_REFINE_CD_TL_RATIOS, PRE_REFINE_CD_TL_RATIOS, UNIQUE_SAMPLE_THRESHOLD, PRE_REFINE_CD_R_STEPS, SND_REFINE_CD_R_STEPS, SAMPLE_BATCH_SIZE};
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
 use crate::sample::best_samples::BestSamples;
-use crate::sample::coord_descent::{refine_coord_desc, CDConfig};
+use crate::sample::coord_descent::CDConfig;
+use crate::sample::free_space_sampler::{FreeSpaceRaster, FreeSpaceSampler};
+use crate::sample::nfp_sampler::{NFPCache, NFPSampler};
+use crate::sample::refine::{refine, RefinerType};
 use crate::sample::uniform_sampler::UniformBBoxSampler;
 use log::debug;
 use rand::Rng;
@@ -13,10 +17,43 @@ use rand::Rng;
 pub struct SampleConfig {
     pub n_container_samples: usize,
     pub n_focussed_samples: usize,
+    /// Number of samples taken from the no-fit polygons with neighbouring items, see [`NFPSampler`]
+    pub n_nfp_samples: usize,
     pub n_coord_descents: usize,
+    /// Defines how the container samples are distributed
+    pub container_sampling: ContainerSampling,
+    /// Strategy used to refine the best samples
+    pub refiner: RefinerType,
 }
 
-pub fn search_placement(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, mut evaluator: impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng) -> (Option<(DTransformation, SampleEval)>, usize) {
+#[derive(Debug, Clone, Copy)]
+pub enum ContainerSampling {
+    /// Samples are distributed uniformly over the entire container
+    Uniform,
+    /// A share of the samples is biased towards gaps in the layout, see [`FreeSpaceSampler`]
+    FreeSpace {
+        /// Number of raster cells along the height of the strip
+        resolution: usize,
+        /// Share of the container samples drawn from the gaps, the rest remains uniform
+        bias: f32,
+    },
+}
+
+pub fn search_placement(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, evaluator: impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng, nfp_cache: &mut NFPCache, fs_raster: &mut FreeSpaceRaster) -> (Option<(DTransformation, SampleEval)>, usize) {
+    search_placement_in(l, l.container.outer_cd.bbox, item, ref_pk, evaluator, sample_config, rng, nfp_cache, fs_raster)
+}
+
+/// Same as [`search_placement`], but the container samples are restricted to `sample_bbox`
+pub fn search_placement_in(l: &Layout, sample_bbox: Rect, item: &Item, ref_pk: Option<PItemKey>, mut evaluator: impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng, nfp_cache: &mut NFPCache, fs_raster: &mut FreeSpaceRaster) -> (Option<(DTransformation, SampleEval)>, usize) {
+    let best_samples = sample_placements(l, sample_bbox, item, ref_pk, &mut evaluator, sample_config, rng, nfp_cache, fs_raster);
+    let final_sample = refine_samples(best_samples, item, &mut evaluator, sample_config, rng);
+
+    debug!("[S] {} samples evaluated, final: {:?}",evaluator.n_evals(),final_sample);
+    (final_sample, evaluator.n_evals())
+}
+
+/// Sampling phase of [`search_placement_in`], returns the best samples to refine
+pub(crate) fn sample_placements(l: &Layout, sample_bbox: Rect, item: &Item, ref_pk: Option<PItemKey>, evaluator: &mut impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng, nfp_cache: &mut NFPCache, fs_raster: &mut FreeSpaceRaster) -> BestSamples {
     let item_min_dim = f32::min(item.shape_cd.bbox.width(), item.shape_cd.bbox.height());
 
     let mut best_samples = BestSamples::new(sample_config.n_coord_descents, item_min_dim * UNIQUE_SAMPLE_THRESHOLD);
@@ -38,28 +75,72 @@ pub fn search_placement(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, mut e
     };
 
     if let Some(focussed_sampler) = focussed_sampler {
-        for _ in 0..sample_config.n_focussed_samples {
-            let dt = focussed_sampler.sample(rng);
-            let eval = evaluator.eval(dt, Some(best_samples.upper_bound()));
-            best_samples.report(dt, eval);
+        //the focussed samples lie close together, so a batch shares a single virtual root of the quadtree
+        eval_in_batches(evaluator, &mut best_samples, sample_config.n_focussed_samples, || focussed_sampler.sample(rng));
+    }
+
+    if sample_config.n_nfp_samples > 0 {
+        //sample positions touching the neighbours of the item (or any item if not placed yet)
+        let focus_bbox = match ref_pk {
+            Some(ref_pk) => l.placed_items[ref_pk].shape.bbox,
+            None => l.container.outer_cd.bbox,
+        };
+        if let Some(nfp_sampler) = NFPSampler::new(l, item, ref_pk, focus_bbox, nfp_cache, rng) {
+            for _ in 0..sample_config.n_nfp_samples {
+                if let Some(dt) = nfp_sampler.sample(rng) {
+                    let eval = evaluator.eval(dt, Some(best_samples.upper_bound()));
+                    best_samples.report(dt, eval);
+                }
+            }
         }
     }
 
-    let container_sampler = UniformBBoxSampler::new(l.container.outer_cd.bbox, item, l.container.outer_cd.bbox);
+    let mut n_uniform_container_samples = sample_config.n_container_samples;
+
+    if let ContainerSampling::FreeSpace { resolution, bias } = sample_config.container_sampling {
+        fs_raster.sync(l, resolution);
+        if let Some(free_space_sampler) = FreeSpaceSampler::new(fs_raster, l, item, ref_pk) {
+            let n_free_space_samples = (sample_config.n_container_samples as f32 * bias).round() as usize;
+            eval_in_batches(evaluator, &mut best_samples, n_free_space_samples, || free_space_sampler.sample(rng));
+            n_uniform_container_samples = n_uniform_container_samples.saturating_sub(n_free_space_samples);
+        }
+    }
+
+    let container_sampler = UniformBBoxSampler::new(sample_bbox, item, l.container.outer_cd.bbox);
 
     if let Some(container_sampler) = container_sampler {
-        for _ in 0..sample_config.n_container_samples {
-            let dt = container_sampler.sample(rng).into();
-            let eval = evaluator.eval(dt, Some(best_samples.upper_bound()));
-            best_samples.report(dt, eval);
+        eval_in_batches(evaluator, &mut best_samples, n_uniform_container_samples, || container_sampler.sample(rng));
+    }
+
+    best_samples
+}
+
+/// Evaluates `n_samples` samples in batches of [`SAMPLE_BATCH_SIZE`] (see [`SampleEvaluator::eval_many`]) and reports them.
+/// Every batch is bounded by the best samples found before it.
+fn eval_in_batches(evaluator: &mut impl SampleEvaluator, best_samples: &mut BestSamples, n_samples: usize, mut sample: impl FnMut() -> DTransformation) {
+    let mut dts = Vec::with_capacity(SAMPLE_BATCH_SIZE);
+    let mut evals = [SampleEval::Invalid; SAMPLE_BATCH_SIZE];
+    let mut n_remaining = n_samples;
+    while n_remaining > 0 {
+        let n = usize::min(n_remaining, SAMPLE_BATCH_SIZE);
+        dts.clear();
+        dts.extend((0..n).map(|_| sample()));
+        evaluator.eval_many(&dts, Some(best_samples.upper_bound()), &mut evals[..n]);
+        for (dt, eval) in dts.iter().zip(evals.iter()) {
+            best_samples.report(*dt, *eval);
         }
+        n_remaining -= n;
     }
-    
+}
+
+/// Refinement phase of [`search_placement_in`]: all best samples are prerefined, the best of them is refined further
+pub(crate) fn refine_samples(mut best_samples: BestSamples, item: &Item, evaluator: &mut impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng) -> Option<(DTransformation, SampleEval)> {
     //Prerefine the best samples
     for start in best_samples.samples.clone() {
-        let descended = refine_coord_desc(
+        let descended = refine(
+            sample_config.refiner,
             start.clone(),
-            &mut evaluator,
+            evaluator,
             prerefine_cd_config(item),
             rng,
         );
@@ -68,17 +149,15 @@ pub fn search_placement(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, mut e
 
 
     //Do a final refine on the best one
-    let final_sample = best_samples.best().map(|s|
-        refine_coord_desc(
-            s, 
-            &mut evaluator, 
-            final_refine_cd_config(item), 
+    best_samples.best().map(|s|
+        refine(
+            sample_config.refiner,
+            s,
+            evaluator,
+            final_refine_cd_config(item),
             rng,
         )
-    );
-
-    debug!("[S] {} samples evaluated, final: {:?}",evaluator.n_evals(),final_sample);
-    (final_sample, evaluator.n_evals())
+    )
 }
 
 fn prerefine_cd_config(item: &Item) -> CDConfig {
@@ -1,6 +1,6 @@
 use crate::eval::specialized_jaguars_pipeline::SpecializedHazardCollector;
 use crate::quantify::tracker::CollisionTracker;
-use crate::quantify::{quantify_collision_poly_container, quantify_collision_poly_poly};
+use crate::quantify::quantify_collision_poly_container;
 use float_cmp::{approx_eq, assert_approx_eq};
 use itertools::Itertools;
 use jagua_rs::util::assertions;
@@ -26,8 +26,8 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
             let stored_loss = ct.get_pair_loss(pk1, pk2);
             match collector.iter().any(|(_, he)| he == &HazardEntity::from((pk2, pi2))) {
                 true => {
-                    let calc_loss = quantify_collision_poly_poly(&pi1.shape, &pi2.shape);
-                    let calc_loss_r = quantify_collision_poly_poly(&pi2.shape, &pi1.shape);
+                    let calc_loss = ct.quantifier.quantify_poly_poly(&pi1.shape, &pi2.shape);
+                    let calc_loss_r = ct.quantifier.quantify_poly_poly(&pi2.shape, &pi1.shape);
                     if !approx_eq!(f32,calc_loss,stored_loss,epsilon = 0.10 * stored_loss) && !approx_eq!(f32,calc_loss_r,stored_loss, epsilon = 0.10 * stored_loss) {
                         let mut opp_collector = BasicHazardCollector::new();
                         l.cde().collect_poly_collisions(&pi2.shape, &mut opp_collector);
@@ -84,7 +84,7 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
                 }
                 false => {
                     if stored_loss != 0.0 {
-                        let calc_loss = quantify_collision_poly_poly(&pi1.shape, &pi2.shape);
+                        let calc_loss = ct.quantifier.quantify_poly_poly(&pi1.shape, &pi2.shape);
                         let mut opp_collector = BasicHazardCollector::new();
                         l.cde().collect_poly_collisions(&pi2.shape, &mut opp_collector);
                         opp_collector.remove_by_entity(&HazardEntity::from((pk2, pi2)));
@@ -156,4 +156,26 @@ pub fn strip_width_is_in_check(prob: &SPProblem) -> bool {
     ).sum::<f32>();
     
     prob.strip_width() < 2.0 * (diameters_of_all_items)
-}
\ No newline at end of file
+}
+
+/// Checks whether a (synced) worker holds exactly the same layout and tracker as the master.
+/// Items are matched by their index in the trackers, the row sums of both trackers should agree.
+pub fn worker_matches_master(layout: &Layout, ct: &CollisionTracker, master_layout: &Layout, master_ct: &CollisionTracker) -> bool {
+    assert_eq!(ct.size, master_ct.size);
+    assert_eq!(layout.container.outer_cd.bbox.width(), master_layout.container.outer_cd.bbox.width());
+    for idx in 0..ct.size {
+        let pi = &layout.placed_items[ct.idx_pk_map[idx]];
+        let m_pi = &master_layout.placed_items[master_ct.idx_pk_map[idx]];
+        assert_eq!(pi.item_id, m_pi.item_id, "worker holds a different item at index {idx}");
+        assert_eq!(pi.d_transf, m_pi.d_transf, "worker placed item at index {idx} differently");
+
+        let (sums, m_sums) = (ct.pair_collisions.sums(idx), master_ct.pair_collisions.sums(idx));
+        let (cont, m_cont) = (ct.container_collisions[idx], master_ct.container_collisions[idx]);
+        assert_eq!(sums.n_colliding, m_sums.n_colliding, "colliding pairs of index {idx} differ");
+        assert_approx_eq!(f32, sums.loss, m_sums.loss, epsilon = 1e-3 * m_sums.loss.max(1.0));
+        assert_approx_eq!(f32, sums.weighted_loss, m_sums.weighted_loss, epsilon = 1e-3 * m_sums.weighted_loss.max(1.0));
+        assert_approx_eq!(f32, sums.excess_weight, m_sums.excess_weight, epsilon = 1e-3 * m_sums.excess_weight.abs().max(1.0));
+        assert_eq!((cont.loss, cont.weight), (m_cont.loss, m_cont.weight), "container collision of index {idx} differs");
+    }
+    true
+}
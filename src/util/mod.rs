@@ -6,3 +6,4 @@ pub mod listener;
 pub mod svg_exporter;
 pub mod terminator;
 pub mod ctrlc_terminator;
+pub mod symmetry;
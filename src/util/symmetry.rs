@@ -0,0 +1,107 @@
+use std::f32::consts::PI;
+use itertools::Itertools;
+use jagua_rs::entities::Item;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_enums::RotationRange;
+use jagua_rs::geometry::geo_traits::TransformableFrom;
+use jagua_rs::geometry::primitives::{Point, SPolygon};
+use jagua_rs::probs::spp::entities::SPInstance;
+use log::{debug, info};
+use crate::quantify::overlap_area::overlap_area;
+
+/// Orders of rotational symmetry which are tested
+const ROTATION_ORDERS: [usize; 7] = [2, 3, 4, 5, 6, 8, 12];
+
+/// Symmetries of a polygon around its centroid.
+/// Mirror symmetries are not detected, since items are never flipped they cannot make orientations equivalent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symmetry {
+    /// The polygon is identical after a rotation by `2π / rotation_order` (1 if not rotationally symmetric).
+    /// This is the lcm of all tested orders that match, so it might be a divisor of the polygon's true order.
+    pub rotation_order: usize,
+}
+
+/// Detects the rotational symmetry of a polygon.
+/// Two shapes are considered identical if the area of their difference is below `tolerance` times the area of the polygon.
+pub fn detect_symmetry(shape: &SPolygon, tolerance: f32) -> Symmetry {
+    let centroid = shape.centroid();
+    let max_diff = shape.area * tolerance;
+    let mut buff = shape.clone();
+    let mut matches = |k: usize| {
+        buff.transform_from(shape, &rotation_around(centroid, 2.0 * PI / k as f32).compose());
+        shape.area - overlap_area(shape, &buff) <= max_diff
+    };
+
+    let matching_orders = ROTATION_ORDERS.iter().copied()
+        .filter(|&k| matches(k))
+        .collect_vec();
+
+    //a polygon symmetric under two orders is also symmetric under their lcm (e.g. 2 and 5 for a decagon).
+    //Within the tolerance this might not hold exactly, in which case the highest matching order is kept.
+    let lcm_order = matching_orders.iter().fold(1, |acc, &k| lcm(acc, k));
+    let rotation_order = match matching_orders.last() {
+        None => 1,
+        Some(&highest) if lcm_order == highest || !matches(lcm_order) => highest,
+        Some(_) => lcm_order,
+    };
+
+    Symmetry { rotation_order }
+}
+
+/// Collapses the allowed orientations of every item under which it looks identical.
+/// From every set of equivalent orientations only the first one is kept, so placements always use an allowed angle.
+/// Apart from their allowed orientations, the items (including their original shapes) are left untouched.
+pub fn collapse_symmetric_orientations(instance: &SPInstance, tolerance: f32) -> SPInstance {
+    let mut n_collapsed = 0;
+
+    let items = instance.items.iter()
+        .map(|(item, qty)| {
+            let symmetry = detect_symmetry(&item.shape_cd, tolerance);
+            debug!("[SYMM] item {}: {:?}", item.id, symmetry);
+
+            let allowed_rotation = match &item.allowed_rotation {
+                RotationRange::Discrete(rs) if symmetry.rotation_order > 1 => {
+                    let period = 2.0 * PI / symmetry.rotation_order as f32;
+                    let mut distinct: Vec<f32> = vec![];
+                    for &r in rs.iter() {
+                        let equivalent = distinct.iter().any(|&d| {
+                            let diff = (r - d).rem_euclid(period);
+                            diff < 1e-3 || period - diff < 1e-3
+                        });
+                        if !equivalent {
+                            distinct.push(r);
+                        }
+                    }
+                    n_collapsed += rs.len() - distinct.len();
+                    match distinct.as_slice() {
+                        [r] if *r == 0.0 => RotationRange::None,
+                        _ => RotationRange::Discrete(distinct),
+                    }
+                }
+                other => other.clone(),
+            };
+            let item = Item { allowed_rotation, ..item.clone() };
+            (item, *qty)
+        })
+        .collect_vec();
+
+    info!("[SYMM] collapsed {} equivalent orientations", n_collapsed);
+    SPInstance::new(items, instance.base_strip.clone())
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+/// Rotation by `angle` around `center`
+fn rotation_around(center: Point, angle: f32) -> DTransformation {
+    let (sin, cos) = angle.sin_cos();
+    let (cx, cy) = (center.0, center.1);
+    DTransformation::new(angle, (cx - (cos * cx - sin * cy), cy - (sin * cx + cos * cy)))
+}
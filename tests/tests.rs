@@ -4,8 +4,9 @@ mod integration_tests {
     use jagua_rs::io::import::Importer;
     use rand::prelude::SmallRng;
     use rand::SeedableRng;
-    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::config::{CompactionConfig, DEFAULT_SPARROW_CONFIG};
     use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::compact::compaction_phase;
     use sparrow::optimizer::compress::compression_phase;
     use sparrow::optimizer::explore::exploration_phase;
     use sparrow::optimizer::lbf::LBFBuilder;
@@ -22,6 +23,12 @@ mod integration_tests {
     const COMPRESS_TIMEOUT: Duration = Duration::from_secs(10);
     const INSTANCE_BASE_PATH: &str = "data/input";
     const RNG_SEED: Option<usize> = Some(0); // fix seed for reproducibility
+    // compaction is disabled by default, so the test enables it explicitly
+    const COMPACTION_CONFIG: CompactionConfig = CompactionConfig {
+        max_passes: 10,
+        step_ratio: 0.25,
+        n_bisections: 8,
+    };
 
     #[test_case("swim.json"; "swim")]
     #[test_case("shirts.json"; "shirts")]
@@ -59,7 +66,1579 @@ mod integration_tests {
         let final_explore_sol = sols.last().expect("no solutions found during exploration");
 
         terminator.new_timeout(COMPRESS_TIMEOUT);
-        compression_phase(&instance, &mut separator, final_explore_sol, &mut sol_listener, &terminator, &config.cmpr_cfg);
+        let cmpr_sol = compression_phase(&instance, &mut separator, final_explore_sol, &mut sol_listener, &terminator, &config.cmpr_cfg);
+
+        let compaction_config = config.cmpr_cfg.compaction.unwrap_or(COMPACTION_CONFIG);
+        let compacted_sol = compaction_phase(&instance, &cmpr_sol, &mut sol_listener, &compaction_config);
+        assert!(compacted_sol.strip_width() <= cmpr_sol.strip_width(), "compaction should never widen the strip");
+        Ok(())
+    }
+}
+#[cfg(all(test, feature = "simd"))]
+mod simd_tests {
+    use anyhow::Result;
+    use float_cmp::approx_eq;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::geo_traits::TransformableFrom;
+    use jagua_rs::io::import::Importer;
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+    use sparrow::quantify::overlap_proxy::{overlap_area_proxy, overlap_area_proxy_pruned};
+    use sparrow::quantify::simd::circles_soa::CirclesSoA;
+    use sparrow::quantify::simd::overlap_proxy_simd::{poles_overlap_area_proxy_with, SimdLevel};
+    use sparrow::util::io;
+    use std::path::Path;
+    use test_case::test_case;
+
+    #[test_case("swim.json"; "swim")]
+    #[test_case("shirts.json"; "shirts")]
+    fn simd_matches_overlap_area_proxy(path: &str) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let levels = SimdLevel::ALL.into_iter().filter(|l| l.is_supported()).collect::<Vec<_>>();
+        println!("[TEST] supported SIMD levels: {:?}", levels);
+
+        for (item1, _) in instance.items.iter() {
+            for (item2, _) in instance.items.iter() {
+                //slide the second item over the first one
+                for i in 0..5 {
+                    let offset = item1.shape_cd.diameter * (i as f32 / 4.0 - 0.5);
+                    let mut s2 = item2.shape_cd.as_ref().clone();
+                    s2.transform_from(&item2.shape_cd, &DTransformation::new(0.0, (offset, offset / 2.0)).compose());
+
+                    let (sp1, sp2) = (item1.shape_cd.surrogate(), s2.surrogate());
+                    let epsilon = f32::max(item1.shape_cd.diameter, s2.diameter) * OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+                    let mut poles2 = CirclesSoA::new();
+                    poles2.load(&sp2.poles);
+
+                    for prune in [false, true] {
+                        let expected = match prune {
+                            true => overlap_area_proxy_pruned(sp1, sp2, epsilon),
+                            false => overlap_area_proxy(sp1, sp2, epsilon),
+                        };
+                        for &level in levels.iter() {
+                            let actual = poles_overlap_area_proxy_with(level, sp1, sp2, epsilon, &poles2, prune);
+                            assert!(approx_eq!(f32, actual, expected, epsilon = expected * 1e-4),
+                                "{:?} (prune: {}) does not match: {} vs {} (items {} and {})", level, prune, actual, expected, item1.id, item2.id);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod overlap_proxy_tests {
+    use anyhow::Result;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::fail_fast::SPSurrogate;
+    use jagua_rs::geometry::geo_traits::TransformableFrom;
+    use jagua_rs::geometry::primitives::SPolygon;
+    use jagua_rs::io::import::Importer;
+    use jagua_rs::probs::spp::entities::SPInstance;
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::{OVERLAP_PROXY_EPSILON_DIAM_RATIO, OVERLAP_PROXY_PRUNE_EPSILON_RATIO};
+    use sparrow::quantify::overlap_proxy::{overlap_area_proxy, overlap_area_proxy_pruned};
+    use sparrow::util::io;
+    use std::f32::consts::PI;
+    use std::hint::black_box;
+    use std::path::Path;
+    use std::time::Instant;
+    use test_case::test_case;
+
+    fn load_instance(path: &str) -> Result<SPInstance> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        Ok(jagua_rs::probs::spp::io::import(&importer, &json_instance)?)
+    }
+
+    /// All pairs of item shapes, with the second one slid over the first one
+    fn sliding_pairs(instance: &SPInstance) -> Vec<(SPolygon, SPolygon, f32)> {
+        let mut pairs = vec![];
+        for (item1, _) in instance.items.iter() {
+            for (item2, _) in instance.items.iter() {
+                for i in 0..9 {
+                    let offset = item1.shape_cd.diameter * (i as f32 / 4.0 - 1.0);
+                    let mut s2 = item2.shape_cd.as_ref().clone();
+                    s2.transform_from(&item2.shape_cd, &DTransformation::new(0.0, (offset, offset / 2.0)).compose());
+                    let epsilon = f32::max(item1.shape_cd.diameter, s2.diameter) * OVERLAP_PROXY_EPSILON_DIAM_RATIO;
+                    pairs.push((item1.shape_cd.as_ref().clone(), s2, epsilon));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test_case("swim.json"; "swim")]
+    #[test_case("shirts.json"; "shirts")]
+    fn pruned_matches_exhaustive(path: &str) -> Result<()> {
+        let instance = load_instance(path)?;
+        for (s1, s2, epsilon) in sliding_pairs(&instance) {
+            let (sp1, sp2) = (s1.surrogate(), s2.surrogate());
+            let pruned = overlap_area_proxy_pruned(sp1, sp2, epsilon);
+            let exhaustive = overlap_area_proxy(sp1, sp2, epsilon);
+
+            //a pruned pair is more than margin apart, so it contributes less than epsilon / (2 + ratio) * min radius
+            let max_r = sp1.poles.iter().chain(sp2.poles.iter()).map(|p| p.radius).fold(0.0, f32::max);
+            let max_pruned_loss = (sp1.poles.len() * sp2.poles.len()) as f32 * PI * max_r
+                * epsilon / (2.0 + OVERLAP_PROXY_PRUNE_EPSILON_RATIO);
+
+            assert!(pruned <= exhaustive * (1.0 + 1e-5), "pruned proxy exceeds the exhaustive one: {} vs {}", pruned, exhaustive);
+            assert!(exhaustive - pruned <= max_pruned_loss * (1.0 + 1e-5) + exhaustive * 1e-5,
+                "pruned proxy deviates too much: {} vs {} (bound {})", pruned, exhaustive, max_pruned_loss);
+        }
+        Ok(())
+    }
+
+    /// Prints the time spent by the pruned and exhaustive proxies on the same pairs of shapes.
+    /// Run with `cargo test --release -- --ignored --nocapture bench_overlap_proxy`.
+    #[test_case("swim.json"; "swim")]
+    #[test_case("trousers.json"; "trousers")]
+    #[ignore]
+    fn bench_overlap_proxy(path: &str) -> Result<()> {
+        const N_REPEATS: usize = 20;
+        let instance = load_instance(path)?;
+        let pairs = sliding_pairs(&instance);
+
+        let time = |proxy: fn(&SPSurrogate, &SPSurrogate, f32) -> f32| {
+            let start = Instant::now();
+            for _ in 0..N_REPEATS {
+                for (s1, s2, epsilon) in pairs.iter() {
+                    black_box(proxy(s1.surrogate(), s2.surrogate(), *epsilon));
+                }
+            }
+            start.elapsed()
+        };
+        let exhaustive = time(overlap_area_proxy);
+        let pruned = time(overlap_area_proxy_pruned);
+
+        println!("[BENCH] {path}: {} evals, exhaustive: {:?}, pruned: {:?} ({:.2}x)",
+                 pairs.len() * N_REPEATS, exhaustive, pruned, exhaustive.as_secs_f64() / pruned.as_secs_f64());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod shape_cache_tests {
+    use anyhow::Result;
+    use jagua_rs::entities::Item;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::geo_enums::RotationRange;
+    use jagua_rs::geometry::primitives::Rect;
+    use jagua_rs::io::import::Importer;
+    use jagua_rs::probs::spp::entities::SPInstance;
+    use rand::prelude::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::eval::lbf_evaluator::LBFEvaluator;
+    use sparrow::eval::sample_eval::{SampleEval, SampleEvaluator};
+    use sparrow::eval::sep_evaluator::SeparationEvaluator;
+    use sparrow::eval::shape_cache::ShapeCache;
+    use sparrow::optimizer::lbf::{LBFBuilder, PlacementScoring};
+    use sparrow::quantify::tracker::CollisionTracker;
+    use sparrow::util::io;
+    use std::path::Path;
+    use test_case::test_case;
+
+    fn load_instance(path: &str) -> Result<SPInstance> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        Ok(jagua_rs::probs::spp::io::import(&importer, &json_instance)?)
+    }
+
+    /// Random transformations within `bbox`, restricted to the allowed orientations of the item.
+    /// Returns `None` for items with continuous rotation, which are not cached.
+    fn random_samples(item: &Item, bbox: Rect, n: usize, rng: &mut SmallRng) -> Option<Vec<DTransformation>> {
+        let rotations = match &item.allowed_rotation {
+            RotationRange::None => vec![0.0],
+            RotationRange::Discrete(rs) => rs.clone(),
+            RotationRange::Continuous => return None,
+        };
+        let samples = (0..n)
+            .map(|_| DTransformation::new(
+                rotations[rng.random_range(0..rotations.len())],
+                (rng.random_range(bbox.x_min..bbox.x_max), rng.random_range(bbox.y_min..bbox.y_max)),
+            ))
+            .collect();
+        Some(samples)
+    }
+
+    fn assert_evals_match(full: SampleEval, cached: SampleEval, item_id: usize) {
+        match (full, cached) {
+            (SampleEval::Clear { loss: l1 }, SampleEval::Clear { loss: l2 }) |
+            (SampleEval::Collision { loss: l1 }, SampleEval::Collision { loss: l2 }) =>
+                assert!((l1 - l2).abs() <= l1.abs() * 1e-3, "losses differ (item {}): {} vs {}", item_id, l1, l2),
+            (SampleEval::Invalid, SampleEval::Invalid) => {}
+            _ => panic!("evaluations differ (item {}): {:?} vs {:?}", item_id, full, cached),
+        }
+    }
+
+    /// Evaluations with pre-rotated shapes should be identical to the ones applying the full transformation
+    #[test_case("fu.json"; "fu")]
+    #[test_case("jakobs1.json"; "jakobs1")]
+    fn rotated_shape_cache_matches_full_transformation(path: &str) -> Result<()> {
+        const N_SAMPLES_PER_ITEM: usize = 500;
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = load_instance(path)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let layout = &builder.prob.layout;
+        let bbox = layout.container.outer_cd.bbox;
+        let ct = CollisionTracker::new(layout, config.expl_cfg.separator_config.overlap_quantifier);
+        let shape_cache = ShapeCache::new(&instance);
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        for (pk, pi) in layout.placed_items.iter() {
+            let item = instance.item(pi.item_id);
+            let Some(samples) = random_samples(item, bbox, N_SAMPLES_PER_ITEM, &mut rng) else { continue };
+
+            let mut lbf_full = LBFEvaluator::new(layout, item, PlacementScoring::LeftBottom);
+            let mut lbf_cached = LBFEvaluator::new(layout, item, PlacementScoring::LeftBottom)
+                .with_rotated_shapes(shape_cache.get(item.id));
+            let mut sep_full = SeparationEvaluator::new(layout, item, pk, &ct);
+            let mut sep_cached = SeparationEvaluator::new(layout, item, pk, &ct)
+                .with_rotated_shapes(shape_cache.get(item.id));
+
+            for dt in samples {
+                assert_evals_match(lbf_full.eval(dt, None), lbf_cached.eval(dt, None), item.id);
+                assert_evals_match(sep_full.eval(dt, None), sep_cached.eval(dt, None), item.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod batch_eval_tests {
+    use anyhow::Result;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::eval::lbf_evaluator::LBFEvaluator;
+    use sparrow::eval::sample_eval::{tighten_bound, SampleEval, SampleEvaluator};
+    use sparrow::eval::sep_evaluator::SeparationEvaluator;
+    use sparrow::optimizer::lbf::{LBFBuilder, PlacementScoring};
+    use sparrow::quantify::tracker::CollisionTracker;
+    use sparrow::util::io;
+    use std::path::Path;
+    use test_case::test_case;
+
+    const BATCH_SIZE: usize = 8;
+
+    /// Evaluates the samples one by one, tightening the upper bound as specified by [`SampleEvaluator::eval_many`]
+    fn eval_sequentially(evaluator: &mut impl SampleEvaluator, dts: &[DTransformation], upper_bound: Option<SampleEval>) -> Vec<SampleEval> {
+        let mut upper_bound = upper_bound;
+        dts.iter()
+            .map(|dt| {
+                let eval = evaluator.eval(*dt, upper_bound);
+                upper_bound = tighten_bound(upper_bound, eval);
+                eval
+            })
+            .collect()
+    }
+
+    fn assert_evals_match(batched: &[SampleEval], sequential: &[SampleEval]) {
+        for (a, b) in batched.iter().zip(sequential.iter()) {
+            match (a, b) {
+                (SampleEval::Clear { loss: l1 }, SampleEval::Clear { loss: l2 }) |
+                (SampleEval::Collision { loss: l1 }, SampleEval::Collision { loss: l2 }) =>
+                    assert!((l1 - l2).abs() <= l1.abs() * 1e-4, "losses differ: {} vs {}", l1, l2),
+                (SampleEval::Invalid, SampleEval::Invalid) => {}
+                _ => panic!("batched and sequential evaluations differ: {:?} vs {:?}", batched, sequential),
+            }
+        }
+    }
+
+    #[test_case("swim.json"; "swim")]
+    #[test_case("shirts.json"; "shirts")]
+    fn eval_many_matches_sequential_eval(path: &str) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let layout = &builder.prob.layout;
+        let bbox = layout.container.outer_cd.bbox;
+        let ct = CollisionTracker::new(layout, config.expl_cfg.separator_config.overlap_quantifier);
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        for (pk, pi) in layout.placed_items.iter() {
+            let item = instance.item(pi.item_id);
+            let (x, y) = pi.d_transf.translation();
+            let r = pi.d_transf.rotation();
+            let d = item.shape_cd.diameter;
+
+            //a batch close to the current placement (sharing a virtual root) and one spread over the container
+            let close = (0..BATCH_SIZE)
+                .map(|_| DTransformation::new(r, (x + rng.random_range(-0.2..0.2) * d, y + rng.random_range(-0.2..0.2) * d)))
+                .collect::<Vec<_>>();
+            let spread = (0..BATCH_SIZE)
+                .map(|_| DTransformation::new(r, (rng.random_range(bbox.x_min..bbox.x_max), rng.random_range(bbox.y_min..bbox.y_max))))
+                .collect::<Vec<_>>();
+
+            for dts in [close, spread] {
+                let mut evals = [SampleEval::Invalid; BATCH_SIZE];
+
+                let mut sep_evaluator = SeparationEvaluator::new(layout, item, pk, &ct);
+                let current = sep_evaluator.eval(pi.d_transf, None);
+                for upper_bound in [None, Some(current), Some(SampleEval::Collision { loss: d * d * 0.01 })] {
+                    sep_evaluator.eval_many(&dts, upper_bound, &mut evals);
+                    let sequential = eval_sequentially(&mut sep_evaluator, &dts, upper_bound);
+                    assert_evals_match(&evals, &sequential);
+                }
+
+                let mut lbf_evaluator = LBFEvaluator::new(layout, item, PlacementScoring::LeftBottom);
+                lbf_evaluator.eval_many(&dts, None, &mut evals);
+                let sequential = eval_sequentially(&mut lbf_evaluator, &dts, None);
+                assert_evals_match(&evals, &sequential);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod shrink_schedule_tests {
+    use sparrow::config::ShrinkStepStrategy;
+    use sparrow::optimizer::shrink_schedule::ShrinkSchedule;
+    use std::num::NonZeroUsize;
+
+    const ADAPTIVE: ShrinkStepStrategy = ShrinkStepStrategy::Adaptive {
+        init: 0.02,
+        min: 0.001,
+        max: 0.05,
+        growth: 1.5,
+        backoff: 0.5,
+        fails_before_backoff: NonZeroUsize::new(3).unwrap(),
+    };
+
+    #[test]
+    fn backoff_is_not_undone_by_next_success() {
+        let mut schedule = ShrinkSchedule::new(ADAPTIVE);
+
+        // a clean success grows the step
+        let grown = schedule.report_success();
+        assert!(grown > 0.02);
+
+        // consecutive failures back off
+        assert_eq!(schedule.report_failure(), None);
+        assert_eq!(schedule.report_failure(), None);
+        let backed_off = schedule.report_failure().expect("should back off after 3 failures");
+        assert!(backed_off < grown);
+
+        // the success right after the backoff was not reached without failures, the step should be kept
+        assert_eq!(schedule.report_success(), backed_off);
+
+        // only a following clean success grows it again
+        assert!(schedule.report_success() > backed_off);
+    }
+}
+
+#[cfg(test)]
+mod exploration_tests {
+    use anyhow::Result;
+    use jagua_rs::io::import::Importer;
+    use jagua_rs::probs::spp::entities::{SPInstance, SPSolution};
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::{ExplorationConfig, OscillationConfig, SeparatorConfig, ShrinkStepStrategy, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::explore::exploration_phase;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::separator::Separator;
+    use sparrow::util::io;
+    use sparrow::util::listener::{DummySolListener, ReportType, SolutionListener};
+    use sparrow::util::terminator::{BasicTerminator, Terminator};
+    use std::num::NonZeroUsize;
+    use std::path::Path;
+    use std::time::Duration;
+    use test_case::test_case;
+
+    /// Counts the reports of every type
+    #[derive(Default)]
+    struct CountingListener {
+        reports: Vec<ReportType>,
+    }
+
+    impl CountingListener {
+        fn count(&self, report: ReportType) -> usize {
+            self.reports.iter().filter(|r| **r == report).count()
+        }
+    }
+
+    impl SolutionListener for CountingListener {
+        fn report(&mut self, report: ReportType, _solution: &SPSolution, _instance: &SPInstance) {
+            self.reports.push(report);
+        }
+    }
+
+    /// Separator with its strip squeezed to half of the constructed width, where no attempt can succeed
+    fn infeasible_separator(sep_config: SeparatorConfig) -> Result<(SPInstance, Separator)> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/jakobs1.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, sep_config);
+        let width = sep.prob.strip_width();
+        sep.change_strip_width(width * 0.5, None);
+        Ok((instance, sep))
+    }
+
+    const QUICK_SEPARATOR: SeparatorConfig = SeparatorConfig {
+        iter_no_imprv_limit: 10,
+        strike_limit: 1,
+        ..DEFAULT_SPARROW_CONFIG.expl_cfg.separator_config
+    };
+
+    #[test]
+    fn backoff_does_not_reset_failure_count() -> Result<()> {
+        let (instance, mut sep) = infeasible_separator(QUICK_SEPARATOR)?;
+        let max_fails = 4;
+        let config = ExplorationConfig {
+            // backs off after every failed attempt
+            shrink_step: ShrinkStepStrategy::Adaptive {
+                init: 0.02,
+                min: 0.001,
+                max: 0.05,
+                growth: 1.5,
+                backoff: 0.5,
+                fails_before_backoff: NonZeroUsize::new(1).unwrap(),
+            },
+            max_conseq_failed_attempts: Some(max_fails),
+            separator_config: QUICK_SEPARATOR,
+            ..DEFAULT_SPARROW_CONFIG.expl_cfg
+        };
+
+        let mut terminator = BasicTerminator::new();
+        terminator.new_timeout(Duration::from_secs(60));
+        let mut listener = CountingListener::default();
+        exploration_phase(&instance, &mut sep, &mut listener, &terminator, &config);
+
+        assert!(!terminator.kill(), "exploration should terminate on the failure count, not on the timeout");
+        assert_eq!(listener.count(ReportType::ExplInfeas), max_fails);
+        Ok(())
+    }
+
+    #[test_case(None; "without oscillation")]
+    #[test_case(Some(OscillationConfig { trigger_after_fails: NonZeroUsize::new(2).unwrap(), widen_ratio: 1.0, max_oscillations: 1 }); "with oscillation")]
+    fn oscillation_widens_stuck_strip(oscillation: Option<OscillationConfig>) -> Result<()> {
+        let (instance, mut sep) = infeasible_separator(QUICK_SEPARATOR)?;
+        let infeasible_width = sep.prob.strip_width();
+        let config = ExplorationConfig {
+            shrink_step: ShrinkStepStrategy::Fixed(0.001),
+            separator_config: QUICK_SEPARATOR,
+            oscillation,
+            ..DEFAULT_SPARROW_CONFIG.expl_cfg
+        };
+
+        let mut terminator = BasicTerminator::new();
+        terminator.new_timeout(Duration::from_secs(5));
+        exploration_phase(&instance, &mut sep, &mut DummySolListener, &terminator, &config);
+
+        // no attempt can succeed at the infeasible width, only an oscillation can widen the strip
+        // (and the fixed shrink step cannot bring it back down to the infeasible width in time)
+        let widened = sep.prob.strip_width() > infeasible_width;
+        assert_eq!(widened, oscillation.is_some());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod disruption_tests {
+    use anyhow::Result;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::{RngCore, SeedableRng};
+    use sparrow::config::{DisruptionConfig, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::disruption::{DisruptionOperator, Disruptor, ALL_DISRUPTION_OPERATORS};
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::separator::Separator;
+    use sparrow::util::io;
+    use std::path::Path;
+
+    #[test]
+    fn roulette_scores_follow_outcomes() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, config.expl_cfg.separator_config);
+
+        let rf = 0.2;
+        let mut disruptor = Disruptor::new(DisruptionConfig {
+            operators: &[DisruptionOperator::SwapLargeItems],
+            reaction_factor: rf,
+            min_score: 0.05,
+            region_width_ratio: 0.1,
+        });
+
+        // a separation that does not improve is not rewarded
+        disruptor.disrupt(&mut sep, &config.expl_cfg, 1.0);
+        disruptor.report_separation(&sep, 2.0);
+        let expected = 1.0 - rf;
+        assert!((disruptor.stats[0].score - expected).abs() < 1e-6);
+
+        // a separation that reaches feasibility is fully rewarded
+        disruptor.disrupt(&mut sep, &config.expl_cfg, 1.0);
+        disruptor.report_separation(&sep, 0.0);
+        let expected = (1.0 - rf) * expected + rf;
+        assert!((disruptor.stats[0].score - expected).abs() < 1e-6);
+
+        let stats = &disruptor.stats[0];
+        assert_eq!((stats.n_applied, stats.n_improved, stats.n_feasible), (2, 1, 1));
+
+        // reports without a pending disruption leave the scores untouched
+        disruptor.report_separation(&sep, 0.0);
+        assert!((disruptor.stats[0].score - expected).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn single_operator_does_not_draw_from_rng() {
+        let config = DisruptionConfig {
+            operators: &[DisruptionOperator::SwapLargeItems],
+            reaction_factor: 0.2,
+            min_score: 0.05,
+            region_width_ratio: 0.1,
+        };
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut reference = rng.clone();
+        assert_eq!(Disruptor::new(config).select(&mut rng), 0);
+        assert_eq!(rng.next_u64(), reference.next_u64(), "selecting the only operator consumed randomness");
+
+        // with multiple operators, the roulette wheel does draw from the rng
+        let portfolio = Disruptor::new(DisruptionConfig { operators: &ALL_DISRUPTION_OPERATORS, ..config });
+        assert!(portfolio.select(&mut rng) < ALL_DISRUPTION_OPERATORS.len());
+    }
+}
+
+#[cfg(test)]
+mod overlap_area_tests {
+    use jagua_rs::geometry::primitives::{Point, SPolygon};
+    use sparrow::quantify::overlap_area::overlap_area;
+
+    const TOLERANCE: f32 = 1e-5;
+
+    fn polygon(points: &[(f32, f32)]) -> SPolygon {
+        SPolygon::new(points.iter().map(|&(x, y)| Point(x, y)).collect()).unwrap()
+    }
+
+    fn square(x: f32, y: f32, size: f32) -> SPolygon {
+        polygon(&[(x, y), (x + size, y), (x + size, y + size), (x, y + size)])
+    }
+
+    /// L-shape with area 3, the notch [1,2]x[1,2] is not part of it.
+    /// The vertices start at the reflex vertex if `from_reflex` is set.
+    fn l_shape(from_reflex: bool) -> SPolygon {
+        let mut points = vec![(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)];
+        if from_reflex {
+            points.rotate_left(3);
+        }
+        polygon(&points)
+    }
+
+    fn assert_area(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() <= TOLERANCE, "expected area {expected}, got {actual}");
+    }
+
+    #[test]
+    fn identical_squares() {
+        let s = square(0.0, 0.0, 2.0);
+        assert_area(overlap_area(&s, &s), 4.0);
+    }
+
+    #[test]
+    fn partially_overlapping_squares() {
+        let s1 = square(0.0, 0.0, 2.0);
+        let s2 = square(1.0, 0.5, 2.0);
+        assert_area(overlap_area(&s1, &s2), 1.5);
+        assert_area(overlap_area(&s2, &s1), 1.5);
+    }
+
+    #[test]
+    fn disjoint_shapes() {
+        let s1 = square(0.0, 0.0, 1.0);
+        let s2 = square(3.0, 3.0, 1.0);
+        assert_area(overlap_area(&s1, &s2), 0.0);
+    }
+
+    #[test]
+    fn concave_shape() {
+        for from_reflex in [false, true] {
+            let l = l_shape(from_reflex);
+            assert_area(overlap_area(&l, &l), 3.0);
+            // the notch lies within the fan of the polygon, but is not part of it
+            assert_area(overlap_area(&l, &square(1.0, 1.0, 1.0)), 0.0);
+            assert_area(overlap_area(&l, &square(0.5, 0.5, 1.0)), 0.75);
+            assert_area(overlap_area(&square(0.5, 0.5, 1.0), &l), 0.75);
+        }
+        assert_area(overlap_area(&l_shape(false), &l_shape(true)), 3.0);
+    }
+}
+
+#[cfg(test)]
+mod free_space_tests {
+    use anyhow::Result;
+    use itertools::Itertools;
+    use jagua_rs::geometry::geo_traits::CollidesWith;
+    use jagua_rs::geometry::primitives::Point;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::sample::free_space_sampler::{FreeSpaceRaster, FreeSpaceSampler};
+    use sparrow::util::io;
+    use std::path::Path;
+
+    const RESOLUTION: usize = 40;
+
+    #[test]
+    fn samples_are_drawn_from_free_cells() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let mut builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut raster = FreeSpaceRaster::default();
+        raster.sync(&builder.prob.layout, RESOLUTION);
+
+        // remove some items, the incrementally synced raster should equal a fresh one
+        let removed = builder.prob.layout.placed_items.keys().step_by(3).collect_vec();
+        for pk in removed {
+            builder.prob.remove_item(pk);
+        }
+        let layout = &builder.prob.layout;
+        raster.sync(layout, RESOLUTION);
+        let mut fresh = FreeSpaceRaster::default();
+        fresh.sync(layout, RESOLUTION);
+
+        let cell_size = layout.container.outer_cd.bbox.height() / RESOLUTION as f32;
+        for (item, _) in instance.items.iter() {
+            let Some(sampler) = FreeSpaceSampler::new(&raster, layout, item, None) else { continue };
+            let fresh_sampler = FreeSpaceSampler::new(&fresh, layout, item, None).expect("fresh raster should have gaps");
+            assert_eq!(sampler.gaps, fresh_sampler.gaps);
+
+            // the centers of all cells of a gap are outside every placed item
+            for &(x_min, y_min, side) in sampler.gaps.iter() {
+                let n = (side / cell_size).round() as usize;
+                for (i, j) in (0..n).cartesian_product(0..n) {
+                    let center = Point(x_min + (i as f32 + 0.5) * cell_size, y_min + (j as f32 + 0.5) * cell_size);
+                    assert!(layout.placed_items.values().all(|pi| !pi.shape.collides_with(&center)),
+                        "gap cell centered at {:?} is occupied", center);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod refiner_tests {
+    use jagua_rs::geometry::DTransformation;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::eval::sample_eval::{SampleEval, SampleEvaluator};
+    use sparrow::sample::coord_descent::CDConfig;
+    use sparrow::sample::refine::{refine, RefinerType};
+    use test_case::test_case;
+
+    /// Convex bowl with its minimum at `OPTIMUM`
+    struct BowlEvaluator {
+        n_evals: usize,
+    }
+
+    const OPTIMUM: (f32, f32, f32) = (3.0, -2.0, 0.5);
+
+    impl SampleEvaluator for BowlEvaluator {
+        fn eval(&mut self, dt: DTransformation, _upper_bound: Option<SampleEval>) -> SampleEval {
+            self.n_evals += 1;
+            let (x, y) = dt.translation();
+            let r = dt.rotation();
+            let loss = (x - OPTIMUM.0).powi(2) + (y - OPTIMUM.1).powi(2) + (r - OPTIMUM.2).powi(2);
+            SampleEval::Collision { loss }
+        }
+
+        fn n_evals(&self) -> usize {
+            self.n_evals
+        }
+    }
+
+    const CD_CONFIG: CDConfig = CDConfig {
+        t_step_init: 1.0,
+        t_step_limit: 0.001,
+        r_step_init: 0.1,
+        r_step_limit: 0.001,
+        wiggle: true,
+    };
+
+    #[test_case(RefinerType::CoordinateDescent; "coordinate descent")]
+    #[test_case(RefinerType::PatternSearch; "pattern search")]
+    #[test_case(RefinerType::NelderMead; "nelder mead")]
+    #[test_case(RefinerType::Gradient; "gradient")]
+    fn refiner_never_worsens(refiner: RefinerType) {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let starts = [
+            DTransformation::new(0.0, (0.0, 0.0)),
+            DTransformation::new(-1.0, (10.0, 7.0)),
+            DTransformation::new(OPTIMUM.2, (OPTIMUM.0, OPTIMUM.1)),
+        ];
+        for start in starts {
+            let mut evaluator = BowlEvaluator { n_evals: 0 };
+            let init_eval = evaluator.eval(start, None);
+            let (dt, eval) = refine(refiner, (start, init_eval), &mut evaluator, CD_CONFIG, &mut rng);
+
+            assert!(eval <= init_eval, "{:?} worsened the sample: {:?} -> {:?}", refiner, init_eval, eval);
+            // the reported evaluation belongs to the returned transformation
+            assert_eq!(eval, BowlEvaluator { n_evals: 0 }.eval(dt, None));
+            if init_eval != (SampleEval::Collision { loss: 0.0 }) {
+                assert!(eval < init_eval, "{:?} did not improve a sample away from the optimum", refiner);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod construction_tests {
+    use anyhow::Result;
+    use jagua_rs::entities::Instance;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::lbf::{construct_best, LBFBuilder};
+    use sparrow::quantify::tracker::CollisionTracker;
+    use sparrow::quantify::OverlapQuantifier;
+    use sparrow::util::io;
+    use std::path::Path;
+    use test_case::test_case;
+
+    #[test_case("swim.json"; "swim")]
+    #[test_case("shirts.json"; "shirts")]
+    fn construct_best_is_feasible_and_narrowest(path: &str) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let best = construct_best(&instance, SmallRng::seed_from_u64(0), &config.constr_cfg);
+
+        let n_placed = best.prob.layout.placed_items.len();
+        assert_eq!(n_placed, instance.total_item_qty(), "not all items were placed");
+        let ct = CollisionTracker::new(&best.prob.layout, OverlapQuantifier::ExactArea);
+        assert_eq!(ct.get_total_loss(), 0.0, "initial solution is not feasible");
+
+        // every strategy on its own, with the seed construct_best derives for it
+        let mut rng = SmallRng::seed_from_u64(0);
+        for strategy in config.constr_cfg.strategies.iter() {
+            let seed = rng.random::<u64>();
+            let single = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(seed), config.constr_cfg.sample_config)
+                .with_strategy(*strategy)
+                .with_lattice(config.constr_cfg.lattice)
+                .with_search_partitions(config.constr_cfg.search_partitions)
+                .construct();
+            assert!(best.prob.strip_width() <= single.prob.strip_width(),
+                "construct_best ({:.3}) is wider than {:?} ({:.3})", best.prob.strip_width(), strategy, single.prob.strip_width());
+        }
+        Ok(())
+    }
+
+    #[test_case("swim.json"; "swim")]
+    #[test_case("shirts.json"; "shirts")]
+    fn partitioned_construction_is_deterministic(path: &str) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let construct = |seed: u64| {
+            let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(seed), LBF_SAMPLE_CONFIG)
+                .with_search_partitions(Some(4))
+                .construct();
+            let placements = builder.prob.layout.placed_items.values()
+                .map(|pi| (pi.item_id, pi.d_transf.rotation(), pi.d_transf.translation()))
+                .collect::<Vec<_>>();
+            (builder.prob.strip_width(), placements)
+        };
+
+        for seed in 0..3 {
+            let (width_1, placements_1) = construct(seed);
+            let (width_2, placements_2) = construct(seed);
+            assert_eq!(width_1, width_2, "widths differ for seed {seed}");
+            assert_eq!(placements_1, placements_2, "placements differ for seed {seed}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod window_tests {
+    use anyhow::Result;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::{DisruptionConfig, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::disruption::{DisruptionOperator, Disruptor};
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::separator::Separator;
+    use sparrow::util::io;
+    use sparrow::util::listener::DummySolListener;
+    use sparrow::util::terminator::{BasicTerminator, Terminator};
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[test]
+    fn only_items_in_window_move() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, config.expl_cfg.separator_config);
+
+        // shrink the strip at the center of a window in the middle of the strip
+        let width = sep.prob.strip_width();
+        let window = (width * 0.3, width * 0.7);
+        sep.change_strip_width_at(width * 0.97, &[width * 0.5]);
+
+        let new_width = sep.prob.strip_width();
+        let window = (window.0, window.1 - (width - new_width));
+        sep.set_window(Some(window));
+
+        let in_window = |x: f32| (window.0..window.1).contains(&x);
+        let outside = sep.prob.layout.placed_items.iter()
+            .filter(|(_, pi)| !in_window(pi.shape.centroid().0))
+            .map(|(pk, pi)| (pk, (pi.d_transf.rotation(), pi.d_transf.translation())))
+            .collect::<Vec<_>>();
+        let n_inside = sep.prob.layout.placed_items.len() - outside.len();
+
+        let mut terminator = BasicTerminator::new();
+        terminator.new_timeout(Duration::from_secs(5));
+        sep.separate(&terminator, &mut DummySolListener);
+
+        for (pk, dt) in outside {
+            let pi = sep.prob.layout.placed_items.get(pk).expect("item outside the window was moved");
+            assert_eq!((pi.d_transf.rotation(), pi.d_transf.translation()), dt, "item outside the window was moved");
+        }
+        let n_inside_after = sep.prob.layout.placed_items.values()
+            .filter(|pi| in_window(pi.shape.centroid().0))
+            .count();
+        assert_eq!(n_inside, n_inside_after, "items left the window");
+        Ok(())
+    }
+
+    #[test]
+    fn lifted_window_separates_disruption_outside_window() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, config.expl_cfg.separator_config);
+
+        let width = sep.prob.strip_width();
+        let window = (width * 0.3, width * 0.7);
+        sep.set_window(Some(window));
+
+        // a slab as wide as the strip relocates every item, also the ones outside the window
+        let mut disruptor = Disruptor::new(DisruptionConfig {
+            operators: &[DisruptionOperator::RelocateRegion],
+            region_width_ratio: 1.0,
+            ..config.expl_cfg.disruption
+        });
+        sep.lift_window();
+        assert_eq!(sep.window(), None);
+        disruptor.disrupt(&mut sep, &config.expl_cfg, 0.0);
+
+        let in_window = |x: f32| (window.0..window.1).contains(&x);
+        let colliding_outside = sep.prob.layout.placed_items.iter()
+            .filter(|(pk, pi)| !in_window(pi.shape.centroid().0) && sep.ct.get_loss(*pk) > 0.0)
+            .map(|(pk, pi)| (pk, (pi.d_transf.rotation(), pi.d_transf.translation())))
+            .collect::<Vec<_>>();
+        assert!(!colliding_outside.is_empty(), "disruption should leave collisions outside the window");
+
+        let mut terminator = BasicTerminator::new();
+        terminator.new_timeout(Duration::from_secs(5));
+        sep.separate(&terminator, &mut DummySolListener);
+
+        assert_eq!(sep.window(), Some(window), "window should be restored after the separation");
+        let n_moved = colliding_outside.iter()
+            .filter(|(pk, dt)| sep.prob.layout.placed_items.get(*pk)
+                .is_none_or(|pi| (pi.d_transf.rotation(), pi.d_transf.translation()) != *dt))
+            .count();
+        assert!(n_moved > 0, "colliding items outside the lifted window should be separated");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod cluster_tests {
+    use anyhow::Result;
+    use jagua_rs::entities::Instance;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::{ClusterConfig, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::cluster::cluster_complementary_items;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::quantify::tracker::CollisionTracker;
+    use sparrow::quantify::OverlapQuantifier;
+    use sparrow::sample::uniform_sampler::convert_sample_to_closest_feasible;
+    use sparrow::util::io;
+    use sparrow::util::listener::DummySolListener;
+    use sparrow::util::terminator::{BasicTerminator, Terminator};
+    use std::path::Path;
+    use std::time::Duration;
+
+    const CLUSTER_CONFIG: ClusterConfig = ClusterConfig {
+        // merge every pair that is found, the split should be repaired regardless
+        min_utilization: 0.0,
+        n_contact_samples: 16,
+        n_bisections: 10,
+    };
+
+    #[test]
+    fn split_solution_is_feasible() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/shirts.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let clustered = cluster_complementary_items(&instance, &CLUSTER_CONFIG).expect("no pairs were merged");
+        assert!(clustered.parts.iter().any(|p| p.len() == 2));
+
+        let builder = LBFBuilder::new(clustered.instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let clustered_sol = builder.prob.save();
+
+        let mut terminator = BasicTerminator::new();
+        terminator.new_timeout(Duration::from_secs(10));
+        let prob = clustered.split_feasible(&clustered_sol, &instance, SmallRng::seed_from_u64(1),
+            config.cmpr_cfg.separator_config, &config.constr_cfg, &terminator, &mut DummySolListener);
+
+        assert_eq!(prob.layout.placed_items.len(), instance.total_item_qty());
+        let ct = CollisionTracker::new(&prob.layout, OverlapQuantifier::ExactArea);
+        assert_eq!(ct.get_total_loss(), 0.0, "split solution is not feasible");
+        for pi in prob.layout.placed_items.values() {
+            let feasible = convert_sample_to_closest_feasible(pi.d_transf, instance.item(pi.item_id));
+            assert_eq!(feasible.rotation(), pi.d_transf.rotation(), "part placed at a rotation which is not allowed");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod symmetry_tests {
+    use anyhow::Result;
+    use jagua_rs::geometry::geo_enums::RotationRange;
+    use jagua_rs::geometry::primitives::{Point, SPolygon};
+    use jagua_rs::io::import::Importer;
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::util::io;
+    use sparrow::util::symmetry::{collapse_symmetric_orientations, detect_symmetry};
+    use std::f32::consts::PI;
+    use std::path::Path;
+    use test_case::test_case;
+
+    const TOLERANCE: f32 = 1e-3;
+
+    fn polygon(points: &[(f32, f32)]) -> SPolygon {
+        SPolygon::new(points.iter().map(|&(x, y)| Point(x, y)).collect()).unwrap()
+    }
+
+    fn regular_polygon(n: usize) -> SPolygon {
+        let points = (0..n)
+            .map(|i| (2.0 * PI * i as f32 / n as f32).sin_cos())
+            .map(|(sin, cos)| (cos, sin))
+            .collect::<Vec<_>>();
+        polygon(&points)
+    }
+
+    #[test]
+    fn rectangle_has_order_2() {
+        let rectangle = polygon(&[(0.0, 0.0), (3.0, 0.0), (3.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(detect_symmetry(&rectangle, TOLERANCE).rotation_order, 2);
+    }
+
+    #[test]
+    fn square_has_order_4() {
+        let square = polygon(&[(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]);
+        assert_eq!(detect_symmetry(&square, TOLERANCE).rotation_order, 4);
+    }
+
+    #[test]
+    fn l_shape_is_not_symmetric() {
+        let l_shape = polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]);
+        assert_eq!(detect_symmetry(&l_shape, TOLERANCE).rotation_order, 1);
+    }
+
+    #[test]
+    fn hexagon_has_order_6() {
+        assert_eq!(detect_symmetry(&regular_polygon(6), TOLERANCE).rotation_order, 6);
+    }
+
+    /// 10 is not a tested order, it is found as the lcm of 2 and 5
+    #[test]
+    fn decagon_has_order_10() {
+        assert_eq!(detect_symmetry(&regular_polygon(10), TOLERANCE).rotation_order, 10);
+    }
+
+    /// Collapsing only restricts the allowed orientations, the shapes of the items are left untouched
+    #[test_case("shirts.json"; "shirts")]
+    #[test_case("trousers.json"; "trousers")]
+    fn collapse_keeps_shapes_and_allowed_angles(path: &str) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let collapsed = collapse_symmetric_orientations(&instance, TOLERANCE);
+        assert_eq!(collapsed.items.len(), instance.items.len());
+
+        for ((original, qty), (item, collapsed_qty)) in instance.items.iter().zip(collapsed.items.iter()) {
+            assert_eq!((original.id, qty), (item.id, collapsed_qty));
+            assert_eq!(original.shape_cd.vertices.len(), item.shape_cd.vertices.len(), "shape of item {} changed", item.id);
+            assert_eq!(original.shape_cd.area, item.shape_cd.area, "shape of item {} changed", item.id);
+
+            let original_angles = match &original.allowed_rotation {
+                RotationRange::Discrete(rs) => rs.clone(),
+                _ => vec![0.0],
+            };
+            match &item.allowed_rotation {
+                RotationRange::None => assert!(original_angles.contains(&0.0)),
+                RotationRange::Discrete(rs) => assert!(rs.iter().all(|r| original_angles.contains(r)),
+                    "item {} has an angle that was not allowed: {:?} vs {:?}", item.id, rs, original_angles),
+                RotationRange::Continuous => assert_eq!(original.allowed_rotation, RotationRange::Continuous),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tracker_tests {
+    use anyhow::Result;
+    use itertools::Itertools;
+    use jagua_rs::entities::Layout;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::geo_enums::RotationRange;
+    use jagua_rs::io::import::Importer;
+    use jagua_rs::probs::spp::entities::SPPlacement;
+    use rand::prelude::{IteratorRandom, SmallRng};
+    use rand::{Rng, SeedableRng};
+    use ordered_float::OrderedFloat;
+    use sparrow::config::{DEFAULT_GLS_WEIGHT_CONFIG, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::separator::{Separator, SeparatorConfig};
+    use sparrow::quantify::tracker::CollisionTracker;
+    use sparrow::quantify::weights::GLSWeightConfig;
+    use sparrow::util::io;
+    use std::f32::consts::PI;
+    use std::path::Path;
+    use test_case::test_case;
+
+    fn assert_close(actual: f32, expected: f32, what: &str) {
+        assert!((actual - expected).abs() <= 1e-3 * f32::max(expected.abs(), 1.0), "{what}: {actual} vs {expected}");
+    }
+
+    /// Compares the incrementally maintained sums of the tracker against a tracker built from scratch,
+    /// weighted with the weights of the incremental tracker
+    fn assert_matches_recomputation(ct: &CollisionTracker, l: &Layout) {
+        let fresh = CollisionTracker::new(l, ct.quantifier);
+        let pks = l.placed_items.keys().collect_vec();
+
+        let (mut total_weighted_loss, mut total_weighted_pair_loss) = (0.0, 0.0);
+        for &pk in pks.iter() {
+            let others = pks.iter().copied().filter(|o| *o != pk).collect_vec();
+            let container_weighted_loss = fresh.get_container_loss(pk) * ct.get_container_weight(pk);
+            let pair_weighted_loss = others.iter().map(|&o| fresh.get_pair_loss(pk, o) * ct.get_pair_weight(pk, o)).sum::<f32>();
+            let excess_weight = (ct.get_container_weight(pk) - 1.0) + others.iter().map(|&o| ct.get_pair_weight(pk, o) - 1.0).sum::<f32>();
+
+            assert_close(ct.get_loss(pk), fresh.get_loss(pk), "loss");
+            assert_close(ct.get_weighted_loss(pk), container_weighted_loss + pair_weighted_loss, "weighted loss");
+            assert_close(ct.get_excess_weight(pk), excess_weight, "excess weight");
+
+            total_weighted_loss += container_weighted_loss;
+            total_weighted_pair_loss += pair_weighted_loss;
+        }
+        //every pair was counted from both sides
+        total_weighted_loss += total_weighted_pair_loss / 2.0;
+
+        assert_close(ct.get_total_loss(), fresh.get_total_loss(), "total loss");
+        assert_close(ct.get_total_weighted_loss(), total_weighted_loss, "total weighted loss");
+    }
+
+    /// Applies a random sequence of moves and weight updates, after every step the tracker should match a full recomputation
+    #[test_case("jakobs1.json"; "jakobs1")]
+    #[test_case("shirts.json"; "shirts")]
+    fn incremental_losses_match_recomputation(path: &str) -> Result<()> {
+        const N_STEPS: usize = 300;
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut prob = builder.prob;
+        let mut ct = CollisionTracker::new(&prob.layout, config.expl_cfg.separator_config.overlap_quantifier);
+        let bbox = prob.layout.container.outer_cd.bbox;
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..N_STEPS {
+            match rng.random_range(0..10) {
+                0 => ct.reset_weights(),
+                1..=3 => ct.increment_weights(&DEFAULT_GLS_WEIGHT_CONFIG),
+                _ => {
+                    //move a random item to a random position in the strip, which registers and removes collisions
+                    let pk = prob.layout.placed_items.keys().choose(&mut rng).unwrap();
+                    let item_id = prob.layout.placed_items[pk].item_id;
+                    let rotation = match &instance.item(item_id).allowed_rotation {
+                        RotationRange::None => 0.0,
+                        RotationRange::Discrete(rs) => rs[rng.random_range(0..rs.len())],
+                        RotationRange::Continuous => rng.random_range(0.0..2.0 * PI),
+                    };
+                    let d_transf = DTransformation::new(rotation, (rng.random_range(bbox.x_min..bbox.x_max), rng.random_range(bbox.y_min..bbox.y_max)));
+
+                    prob.remove_item(pk);
+                    let new_pk = prob.place_item(SPPlacement { d_transf, item_id });
+                    ct.register_item_move(&prob.layout, pk, new_pk);
+                }
+            }
+            assert_matches_recomputation(&ct, &prob.layout);
+        }
+        Ok(())
+    }
+
+    /// Weights are carried over a change of the strip width, also for the items that were shifted (and got a new key)
+    #[test]
+    fn weights_are_carried_over_width_change() -> Result<()> {
+        const DECAY: f32 = 0.5;
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let sep_config = SeparatorConfig {
+            weight_config: GLSWeightConfig { carry_over_decay: Some(DECAY), ..DEFAULT_GLS_WEIGHT_CONFIG },
+            ..config.expl_cfg.separator_config
+        };
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, sep_config);
+
+        // squeeze the strip and build up some GLS weights
+        let width = sep.prob.strip_width();
+        sep.change_strip_width(width * 0.9, None);
+        for _ in 0..5 {
+            sep.ct.increment_weights(&sep_config.weight_config);
+        }
+
+        // identify every item by its id and centroid, the items right of the split are shifted by the change in width
+        let split = sep.prob.strip_width() / 2.0;
+        let delta = -0.05 * sep.prob.strip_width();
+        let before = sep.prob.layout.placed_items.iter()
+            .map(|(pk, pi)| {
+                let (cx, cy) = (pi.shape.centroid().0, pi.shape.centroid().1);
+                let cx = if cx > split { cx + delta } else { cx };
+                (pk, (pi.item_id, cx, cy))
+            })
+            .collect_vec();
+        let weights_before = sep.ct.clone();
+        sep.change_strip_width(sep.prob.strip_width() + delta, Some(split));
+
+        let new_pk = |(item_id, cx, cy): (usize, f32, f32)| {
+            sep.prob.layout.placed_items.iter()
+                .filter(|(_, pi)| pi.item_id == item_id)
+                .min_by_key(|(_, pi)| OrderedFloat((pi.shape.centroid().0 - cx).abs() + (pi.shape.centroid().1 - cy).abs()))
+                .map(|(pk, _)| pk)
+                .unwrap()
+        };
+        let mapping = before.iter().map(|&(pk, id)| (pk, new_pk(id))).collect_vec();
+        assert!(mapping.iter().any(|(old, new)| old != new), "no item was shifted");
+
+        let decayed = |w: f32| 1.0 + (w - 1.0) * DECAY;
+        for &(old1, new1) in mapping.iter() {
+            assert_close(sep.ct.get_container_weight(new1), decayed(weights_before.get_container_weight(old1)), "container weight");
+            for &(old2, new2) in mapping.iter().filter(|(o, _)| *o != old1) {
+                assert_close(sep.ct.get_pair_weight(new1, new2), decayed(weights_before.get_pair_weight(old1, old2)), "pair weight");
+            }
+        }
+        assert!(mapping.iter().any(|&(old, _)| weights_before.get_excess_weight(old) > 0.0), "no weights were built up");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod lattice_tests {
+    use anyhow::Result;
+    use jagua_rs::entities::Item;
+    use jagua_rs::geometry::geo_enums::RotationRange;
+    use jagua_rs::geometry::primitives::{Point, SPolygon};
+    use jagua_rs::io::import::Importer;
+    use jagua_rs::probs::spp::entities::SPProblem;
+    use sparrow::config::{LatticeConfig, DEFAULT_SPARROW_CONFIG};
+    use sparrow::optimizer::lattice::Lattice;
+    use sparrow::quantify::tracker::CollisionTracker;
+    use sparrow::quantify::OverlapQuantifier;
+    use sparrow::util::io;
+    use std::f32::consts::PI;
+    use std::path::Path;
+    use test_case::test_case;
+
+    const LATTICE_CONFIG: LatticeConfig = LatticeConfig {
+        min_demand: 2,
+        n_continuous_rotations: 4,
+        n_shear_samples: 8,
+        n_contact_samples: 16,
+        n_bisections: 14,
+    };
+
+    fn item(points: &[(f32, f32)], allowed_rotation: RotationRange) -> Item {
+        let shape = SPolygon::new(points.iter().map(|&(x, y)| Point(x, y)).collect()).unwrap();
+        Item::new(0, shape, allowed_rotation, None, DEFAULT_SPARROW_CONFIG.cde_config.item_surrogate_config)
+    }
+
+    #[test]
+    fn rectangle_tiles_the_plane() {
+        let rectangle = item(&[(0.0, 0.0), (20.0, 0.0), (20.0, 10.0), (0.0, 10.0)], RotationRange::None);
+        let lattice = Lattice::best(&rectangle, &LATTICE_CONFIG).expect("no lattice found");
+        assert_eq!(lattice.motif.len(), 1);
+        assert!(lattice.density > 0.99 && lattice.density <= 1.0, "unexpected density: {}", lattice.density);
+    }
+
+    /// A right triangle and its copy rotated by 180° form a rectangle, only a double lattice tiles the plane
+    #[test]
+    fn triangle_tiles_the_plane_in_double_lattice() {
+        let triangle = item(&[(0.0, 0.0), (20.0, 0.0), (0.0, 10.0)], RotationRange::Discrete(vec![0.0, PI]));
+        let lattice = Lattice::best(&triangle, &LATTICE_CONFIG).expect("no lattice found");
+        assert_eq!(lattice.motif.len(), 2);
+        assert!(lattice.density > 0.95 && lattice.density <= 1.0, "unexpected density: {}", lattice.density);
+    }
+
+    /// Seeded copies are placed inside the strip without colliding with each other
+    #[test_case("shirts.json"; "shirts")]
+    #[test_case("trousers.json"; "trousers")]
+    #[test_case("jakobs2.json"; "jakobs2")]
+    fn seeded_copies_are_feasible(path: &str) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let (item, qty) = instance.items.iter().max_by_key(|(_, qty)| *qty).unwrap();
+        let lattice = Lattice::best(item, &LATTICE_CONFIG).expect("no lattice found");
+        assert!(lattice.density <= 1.0, "lattice is denser than possible: {}", lattice.density);
+
+        let mut prob = SPProblem::new(instance.clone());
+        let x_start = prob.layout.container.outer_cd.bbox.x_min;
+        let n_placed = lattice.seed(&mut prob, item, x_start, *qty);
+        assert!(n_placed > 0);
+        assert_eq!(n_placed, prob.layout.placed_items.len());
+
+        let bbox = prob.layout.container.outer_cd.bbox;
+        for pi in prob.layout.placed_items.values() {
+            let s = pi.shape.bbox;
+            assert!(s.x_min >= bbox.x_min && s.x_max <= bbox.x_max && s.y_min >= bbox.y_min && s.y_max <= bbox.y_max,
+                "copy placed outside the strip: {:?}", s);
+        }
+        let ct = CollisionTracker::new(&prob.layout, OverlapQuantifier::ExactArea);
+        assert_eq!(ct.get_total_loss(), 0.0, "seeded copies collide");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ruin_recreate_tests {
+    use anyhow::Result;
+    use itertools::Itertools;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::{RuinRecreateConfig, DEFAULT_GLS_WEIGHT_CONFIG, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::ruin_recreate::ruin_and_recreate;
+    use sparrow::optimizer::separator::Separator;
+    use sparrow::util::io;
+    use std::num::NonZeroUsize;
+    use std::path::Path;
+
+    #[test]
+    fn reinserts_all_items_and_keeps_untouched_weights() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, config.expl_cfg.separator_config);
+
+        // squeeze the strip and build up some GLS weights
+        let width = sep.prob.strip_width();
+        sep.change_strip_width(width * 0.9, None);
+        for _ in 0..5 {
+            sep.ct.increment_weights(&DEFAULT_GLS_WEIGHT_CONFIG);
+        }
+        assert!(sep.prob.layout.placed_items.keys().any(|pk| sep.ct.get_excess_weight(pk) > 0.0));
+
+        let item_ids = |sep: &Separator| sep.prob.layout.placed_items.values().map(|pi| pi.item_id).sorted().collect_vec();
+        let ids_before = item_ids(&sep);
+        let pks_before = sep.prob.layout.placed_items.keys().collect_vec();
+        let weights_before = sep.ct.clone();
+
+        let rr_config = RuinRecreateConfig {
+            trigger_after_fails: NonZeroUsize::new(1).unwrap(),
+            window_width_ratio: 0.3,
+            sample_config: config.expl_cfg.separator_config.sample_config,
+        };
+        ruin_and_recreate(&mut sep, &rr_config);
+
+        assert_eq!(item_ids(&sep), ids_before, "not all removed items were reinserted");
+
+        // the items outside the ruined window keep their key and their weights
+        let untouched = pks_before.into_iter()
+            .filter(|pk| sep.prob.layout.placed_items.contains_key(*pk))
+            .collect_vec();
+        assert!(!untouched.is_empty() && untouched.len() < ids_before.len());
+        for &pk in untouched.iter() {
+            assert_eq!(sep.ct.get_container_weight(pk), weights_before.get_container_weight(pk));
+            for &other in untouched.iter().filter(|o| **o != pk) {
+                assert_eq!(sep.ct.get_pair_weight(pk, other), weights_before.get_pair_weight(pk, other));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod split_tests {
+    use anyhow::Result;
+    use itertools::Itertools;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::split::{split_positions, SplitConfig, SplitPositionStrategy};
+    use sparrow::util::io;
+    use std::path::Path;
+    use test_case::test_case;
+
+    const N_CANDIDATES: usize = 16;
+
+    /// In a dense layout with a single empty vertical gap, the layout-aware strategies should split inside the gap
+    #[test_case(SplitPositionStrategy::LeastCrossedArea { n_candidates: N_CANDIDATES }, 1; "least crossed area")]
+    #[test_case(SplitPositionStrategy::LeastCrossedArea { n_candidates: N_CANDIDATES }, 2; "least crossed area, 2 splits")]
+    #[test_case(SplitPositionStrategy::MostSlack { n_candidates: N_CANDIDATES }, 1; "most slack")]
+    fn splits_avoid_dense_columns(strategy: SplitPositionStrategy, n_splits: usize) -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let mut prob = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct().prob;
+
+        // clear a gap of three candidate intervals wide, so at least two candidates are drawn inside it
+        let bbox = prob.layout.container.outer_cd.bbox;
+        let gap = (bbox.x_min + 0.4 * bbox.width(), bbox.x_min + 0.4 * bbox.width() + 3.0 * bbox.width() / N_CANDIDATES as f32);
+        let in_gap = prob.layout.placed_items.iter()
+            .filter(|(_, pi)| pi.shape.bbox.x_max > gap.0 && pi.shape.bbox.x_min < gap.1)
+            .map(|(pk, _)| pk)
+            .collect_vec();
+        for pk in in_gap {
+            prob.remove_item(pk);
+        }
+        // the removed items might have left an even wider gap
+        let gap = (
+            prob.layout.placed_items.values().map(|pi| pi.shape.bbox.x_max).filter(|x| *x <= gap.0).fold(bbox.x_min, f32::max),
+            prob.layout.placed_items.values().map(|pi| pi.shape.bbox.x_min).filter(|x| *x >= gap.1).fold(bbox.x_max, f32::min),
+        );
+
+        for seed in 0..10 {
+            let split_config = SplitConfig { strategy, n_splits };
+            let positions = split_positions(&prob.layout, &split_config, &mut SmallRng::seed_from_u64(seed));
+            assert_eq!(positions.len(), n_splits);
+            for x in positions {
+                assert!(gap.0 <= x && x <= gap.1, "split at {:.3} is outside the gap [{:.3}, {:.3}]", x, gap.0, gap.1);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod weights_tests {
+    use anyhow::Result;
+    use jagua_rs::io::import::Importer;
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::{DEFAULT_GLS_WEIGHT_CONFIG, DEFAULT_SPARROW_CONFIG};
+    use sparrow::consts::LBF_SAMPLE_CONFIG;
+    use sparrow::optimizer::lbf::LBFBuilder;
+    use sparrow::optimizer::separator::Separator;
+    use sparrow::quantify::weights::{GLSWeightConfig, WeightUpdateRule};
+    use sparrow::util::io;
+    use std::path::Path;
+
+    const MULTIPLICATIVE: WeightUpdateRule = WeightUpdateRule::Multiplicative { min_inc_ratio: 1.2, max_inc_ratio: 2.0, decay: 0.95 };
+    const ADDITIVE: WeightUpdateRule = WeightUpdateRule::Additive { min_inc: 0.5, max_inc: 1.5, decay: 0.9 };
+
+    #[test]
+    fn rules_give_expected_weights() {
+        // a collision with half of the maximum loss
+        assert!((MULTIPLICATIVE.apply(2.0, 5.0, 10.0) - 3.2).abs() < 1e-6);
+        assert!((ADDITIVE.apply(2.0, 5.0, 10.0) - 3.0).abs() < 1e-6);
+        // the worst collision
+        assert!((MULTIPLICATIVE.apply(2.0, 10.0, 10.0) - 4.0).abs() < 1e-6);
+        assert!((ADDITIVE.apply(2.0, 10.0, 10.0) - 3.5).abs() < 1e-6);
+        // no collision, the weight decays but never below 1.0
+        assert!((MULTIPLICATIVE.apply(2.0, 0.0, 10.0) - 1.9).abs() < 1e-6);
+        assert!((ADDITIVE.apply(2.0, 0.0, 10.0) - 1.8).abs() < 1e-6);
+        assert_eq!(MULTIPLICATIVE.apply(1.0, 0.0, 10.0), 1.0);
+        assert_eq!(ADDITIVE.apply(1.0, 0.0, 10.0), 1.0);
+    }
+
+    /// The tracker applies the pair and container rules to their own entries and caps all weights
+    #[test]
+    fn tracker_applies_rules_per_entry_type() -> Result<()> {
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new("data/input/swim.json"))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        let builder = LBFBuilder::new(instance.clone(), SmallRng::seed_from_u64(0), LBF_SAMPLE_CONFIG).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, config.expl_cfg.separator_config);
+        // squeezing at the right end of the strip pushes the rightmost items out of the container,
+        // shrinking at the center creates collisions between pairs of items
+        let width = sep.prob.strip_width();
+        sep.change_strip_width(width * 0.95, Some(width));
+        sep.change_strip_width(width * 0.9, None);
+
+        // every collision gets the same increment, independent of its loss
+        let weight_config = GLSWeightConfig {
+            pair_rule: WeightUpdateRule::Additive { min_inc: 1.0, max_inc: 1.0, decay: 0.9 },
+            container_rule: WeightUpdateRule::Multiplicative { min_inc_ratio: 3.0, max_inc_ratio: 3.0, decay: 0.9 },
+            max_weight: Some(2.5),
+            ..DEFAULT_GLS_WEIGHT_CONFIG
+        };
+        sep.ct.increment_weights(&weight_config);
+
+        let pks = sep.prob.layout.placed_items.keys().collect::<Vec<_>>();
+        let (mut n_pair_collisions, mut n_container_collisions) = (0, 0);
+        for &pk in pks.iter() {
+            let expected = match sep.ct.get_container_loss(pk) > 0.0 {
+                true => { n_container_collisions += 1; 2.5 } // 3.0, capped
+                false => 1.0,
+            };
+            assert_eq!(sep.ct.get_container_weight(pk), expected);
+            for &other in pks.iter().filter(|o| **o != pk) {
+                let expected = match sep.ct.get_pair_loss(pk, other) > 0.0 {
+                    true => { n_pair_collisions += 1; 2.0 }
+                    false => 1.0,
+                };
+                assert_eq!(sep.ct.get_pair_weight(pk, other), expected);
+            }
+        }
+        assert!(n_pair_collisions > 0 && n_container_collisions > 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod nfp_sampler_tests {
+    use anyhow::Result;
+    use jagua_rs::geometry::DTransformation;
+    use jagua_rs::geometry::geo_traits::TransformableFrom;
+    use jagua_rs::geometry::primitives::SPolygon;
+    use jagua_rs::io::import::Importer;
+    use jagua_rs::probs::spp::entities::{SPPlacement, SPProblem};
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+    use sparrow::config::DEFAULT_SPARROW_CONFIG;
+    use sparrow::quantify::overlap_area::overlap_area;
+    use sparrow::sample::nfp_sampler::{convex_hull, NFPCache, NFPSampler};
+    use sparrow::util::io;
+    use std::path::Path;
+    use test_case::test_case;
+
+    fn hull(shape: &SPolygon) -> SPolygon {
+        SPolygon::new(convex_hull(shape.vertices.clone())).unwrap()
+    }
+
+    /// Samples lie on the no-fit polygon of the convex hulls: the hulls touch, but do not overlap
+    #[test_case("jakobs1.json"; "jakobs1")]
+    #[test_case("shirts.json"; "shirts")]
+    fn samples_touch_without_overlap(path: &str) -> Result<()> {
+        const N_SAMPLES: usize = 200;
+        let config = DEFAULT_SPARROW_CONFIG;
+        let json_instance = io::read_spp_instance_json(Path::new(&format!("data/input/{path}")))?;
+        let importer = Importer::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = jagua_rs::probs::spp::io::import(&importer, &json_instance)?;
+
+        // a single fixed item in the middle of the strip
+        let mut prob = SPProblem::new(instance.clone());
+        let bbox = prob.layout.container.outer_cd.bbox;
+        let (fixed, moving) = (instance.item(0), instance.item(instance.items.len() - 1));
+        let center = ((bbox.x_min + bbox.x_max) / 2.0, (bbox.y_min + bbox.y_max) / 2.0);
+        prob.place_item(SPPlacement { item_id: fixed.id, d_transf: DTransformation::new(0.0, center) });
+        let fixed_hull = hull(&prob.layout.placed_items.values().next().unwrap().shape);
+        let fixed_centroid = fixed_hull.centroid();
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut cache = NFPCache::default();
+        let sampler = NFPSampler::new(&prob.layout, moving, None, bbox, &mut cache, &mut rng).expect("no sampler");
+
+        // the no-fit polygons are reused by the next sampler
+        let n_cached = cache.len();
+        assert!(n_cached > 0);
+        NFPSampler::new(&prob.layout, moving, None, bbox, &mut cache, &mut rng).expect("no sampler");
+        assert_eq!(cache.len(), n_cached);
+
+        let mut buff = moving.shape_cd.as_ref().clone();
+        let mut n_samples = 0;
+        for _ in 0..N_SAMPLES {
+            let Some(dt) = sampler.sample(&mut rng) else { continue };
+            n_samples += 1;
+            buff.transform_from(&moving.shape_cd, &dt.compose());
+            let moving_hull = hull(&buff);
+            let tolerance = 1e-4 * f32::min(moving_hull.area, fixed_hull.area);
+            assert!(overlap_area(&fixed_hull, &moving_hull) <= tolerance, "hulls overlap at {}", dt);
+
+            // nudging the item towards the fixed one makes the hulls overlap
+            let c = moving_hull.centroid();
+            let nudge = 0.05 * moving.shape_cd.diameter;
+            let (dx, dy) = (fixed_centroid.0 - c.0, fixed_centroid.1 - c.1);
+            let d = (dx * dx + dy * dy).sqrt();
+            let (tx, ty) = dt.translation();
+            let nudged = DTransformation::new(dt.rotation(), (tx + nudge * dx / d, ty + nudge * dy / d));
+            buff.transform_from(&moving.shape_cd, &nudged.compose());
+            assert!(overlap_area(&fixed_hull, &hull(&buff)) > tolerance, "hulls do not touch at {}", dt);
+        }
+        assert!(n_samples > 0);
         Ok(())
     }
-}
\ No newline at end of file
+}